@@ -0,0 +1,60 @@
+//! Lets the autopilot play without any window and prints how well it did,
+//! build with `--no-default-features`.
+//!
+//! cargo run --release --no-default-features --example autopilot -- 100 [--wrap]
+
+use my_snake::*;
+use my_snake::autopilot::Autopilot;
+use my_snake::controller::play_round;
+
+const COLS: usize = 12;
+const ROWS: usize = 12;
+
+/// Rounds taking longer than this are counted as stuck, the autopilot may chase its tail forever.
+const MAX_STEPS: usize = COLS * ROWS * COLS * ROWS;
+
+fn main() {
+    let games: u64 = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(100);
+    let topology = if std::env::args().any(|arg| arg == "--wrap") {
+        BoardTopology::Wrap
+    } else {
+        BoardTopology::Walled
+    };
+
+    let mut total_steps = 0usize;
+    let mut total_food = 0usize;
+    let mut wins = 0u64;
+    let mut stuck = 0u64;
+
+    for seed in 0..games {
+        let mut game = Game::new(COLS, ROWS, topology, seed).expect("valid board");
+        let mut autopilot = Autopilot::new();
+
+        match play_round(&mut game, &mut autopilot, MAX_STEPS) {
+            Some(stats) => {
+                total_steps += stats.steps;
+                total_food += stats.food_eaten;
+                if stats.game_over == Some(GameOverType::PlaygroundFilled) {
+                    wins += 1;
+                }
+            },
+            None => {
+                total_steps += game.step_count;
+                total_food += game.stats.food_eaten;
+                stuck += 1;
+            },
+        }
+    }
+
+    println!(
+        "games: {}, won: {}, stuck: {}, average steps: {:.1}, average food eaten: {:.1}",
+        games,
+        wins,
+        stuck,
+        total_steps as f64 / games as f64,
+        total_food as f64 / games as f64,
+    );
+}
@@ -0,0 +1,50 @@
+//! Steps a batch of games with random inputs and compares single and multi-threaded speed.
+//! Build with `--no-default-features`.
+//!
+//! cargo run --release --no-default-features --example batch -- 4096 1000
+
+use std::time::Instant;
+
+use rand::Rng;
+
+use my_snake::*;
+use my_snake::batch::GameBatch;
+use my_snake::rng::GameRng;
+
+const DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+fn main() {
+    let mut args = std::env::args().skip(1).map(|arg| arg.parse::<usize>().ok());
+    let games = args.next().flatten().unwrap_or(4096);
+    let steps = args.next().flatten().unwrap_or(1000);
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    for threads in [1, max_threads] {
+        let mut batch = GameBatch::new(games, 12, 12, BoardTopology::Walled, 0).expect("valid board");
+        batch.set_threads(threads);
+
+        let mut input_rng = GameRng::new(0);
+        let mut actions = vec![None; games];
+        let started = Instant::now();
+
+        for _ in 0..steps {
+            for action in actions.iter_mut() {
+                *action = Some(DIRECTIONS[input_rng.gen_range(0..DIRECTIONS.len())]);
+            }
+            batch.step(&actions);
+        }
+
+        let elapsed = started.elapsed().as_secs_f64();
+        let rounds: usize = batch.rounds_finished().iter().sum();
+        let points: u64 = batch.total_points().iter().sum();
+
+        println!(
+            "{} threads: {:.0} steps/s, {} rounds finished, {} points",
+            threads,
+            (games * steps) as f64 / elapsed,
+            rounds,
+            points,
+        );
+    }
+}
@@ -0,0 +1,53 @@
+//! Runs random agents in the training environment and prints the average return
+//! for every observation encoding. Build with `--no-default-features`.
+//!
+//! cargo run --release --no-default-features --example env -- 1000
+
+use rand::Rng;
+
+use my_snake::*;
+use my_snake::env::{ObservationEncoding, SnakeEnv, ACTIONS};
+use my_snake::rng::GameRng;
+
+fn main() {
+    let episodes: u64 = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(1000);
+
+    let encodings = [
+        ObservationEncoding::Grid,
+        ObservationEncoding::Egocentric { radius: 3 },
+        ObservationEncoding::Rays,
+    ];
+
+    for encoding in encodings {
+        let mut env = SnakeEnv::new(12, 12, BoardTopology::Walled).expect("valid board");
+        env.encoding = encoding;
+
+        let mut agent_rng = GameRng::new(0);
+        let mut total_return = 0.0;
+        let mut shape = Vec::new();
+
+        for seed in 0..episodes {
+            shape = env.reset(seed).shape;
+
+            loop {
+                let action = ACTIONS[agent_rng.gen_range(0..ACTIONS.len())];
+                let (_, reward, done, _) = env.step(action);
+                total_return += reward;
+
+                if done {
+                    break;
+                }
+            }
+        }
+
+        println!(
+            "{:?}: observation shape {:?}, average return {:.3}",
+            encoding,
+            shape,
+            total_return / episodes as f32,
+        );
+    }
+}
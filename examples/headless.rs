@@ -0,0 +1,37 @@
+//! Plays random games without any window, build with `--no-default-features`.
+//!
+//! cargo run --release --no-default-features --example headless -- 10000
+
+use rand::Rng;
+
+use my_snake::*;
+use my_snake::rng::GameRng;
+
+const DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+fn main() {
+    let games: u64 = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(1000);
+
+    let mut input_rng = GameRng::new(0);
+    let mut total_steps = 0usize;
+    let mut total_food = 0usize;
+
+    for seed in 0..games {
+        let mut game = Game::new(12, 12, BoardTopology::Walled, seed).expect("valid board");
+        game.play(Direction::Right);
+
+        loop {
+            let input = DIRECTIONS[input_rng.gen_range(0..DIRECTIONS.len())];
+            if let StepOutcome::RoundOver(stats) = game.step(Some(input)) {
+                total_steps += stats.steps;
+                total_food += stats.food_eaten;
+                break;
+            }
+        }
+    }
+
+    println!("games: {}, steps: {}, food eaten: {}", games, total_steps, total_food);
+}
@@ -0,0 +1,40 @@
+//! Plays a round from a controller script and prints how it ended.
+//! Build with `--no-default-features`.
+//!
+//! cargo run --no-default-features --example scripted -- [script file]
+
+use my_snake::*;
+use my_snake::controller::{play_round, ScriptedController};
+
+/// Two laps around the start of a 12x12 board, then straight into the wall once the script runs out.
+const DEFAULT_SCRIPT: &str = "
+; one lap around the start
+2. D 2. L 2. U 2. R
+; and once more, wider
+3. D 3. L 3. U 3. R
+";
+
+fn main() {
+    let text = match std::env::args().nth(1) {
+        Some(path) => std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("Failed to read script {}: {}", path, e);
+            std::process::exit(1);
+        }),
+        None => DEFAULT_SCRIPT.to_string(),
+    };
+
+    let mut script: ScriptedController = match text.parse() {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        },
+    };
+
+    let mut game = Game::new(12, 12, BoardTopology::Walled, 0).expect("valid board");
+
+    match play_round(&mut game, &mut script, 1000) {
+        Some(stats) => println!("Round over: {:?}", stats),
+        None => println!("Round still running after {} steps", game.step_count),
+    }
+}
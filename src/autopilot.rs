@@ -0,0 +1,361 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::controller::Controller;
+use crate::rng::GameRng;
+use crate::{CellType, Direction, Game, GameState};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+/// Computer player.
+///
+/// Heads for the nearest food along a shortest path, but only when the snake can still
+/// reach its own tail after eating. Otherwise it chases its tail, taking the longest way
+/// around, and when even that is impossible it moves to the largest open area.
+/// Paths may cross the snake's own body where it will have moved on by the time the head
+/// gets there.
+///
+/// Chasing the tail can repeat the same loop forever while the food stays out of reach.
+/// When the snake goes a quarter of the field worth of steps without eating, the autopilot
+/// takes it for a loop and picks among the moves that keep the tail in reach at random,
+/// until the snake eats again. This breaks most loops, but on an almost full field the
+/// snake may still circle for a long time, headless runs should give up after some number
+/// of steps.
+///
+/// The autopilot plans from the snake's direction, so it expects to be the only source of
+/// input: ask for a direction right before every step.
+#[derive(Debug, Clone)]
+pub struct Autopilot {
+    pub player: usize,  // snake steered by the autopilot
+    length: usize,      // snake length at the previous step
+    idle_steps: usize,  // steps since the snake last grew
+    rng: GameRng,       // picks the moves that break loops, same seed = same game
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Autopilot::for_player(0)
+    }
+}
+
+impl Autopilot {
+    /// Autopilot for player one.
+    pub fn new() -> Autopilot {
+        Autopilot::default()
+    }
+
+    pub fn for_player(player: usize) -> Autopilot {
+        Autopilot {
+            player,
+            length: 0,
+            idle_steps: 0,
+            rng: GameRng::new(player as u64),
+        }
+    }
+
+    /// Direction to take in the next step, `None` when the round is over or every move is fatal.
+    pub fn next_direction(&mut self, game: &Game) -> Option<Direction> {
+        if let GameState::GameOver(_) = game.state {
+            return None;
+        }
+
+        let snake = game.snakes.get(self.player)?;
+        if !snake.is_alive() {
+            return None;
+        }
+
+        if snake.len() == self.length {
+            self.idle_steps += 1;
+        } else {
+            self.length = snake.len();
+            self.idle_steps = 0;
+        }
+
+        let head = snake.head_index;
+        let moves: Vec<(Direction, usize)> = DIRECTIONS
+            .iter()
+            .copied()
+            .filter(|direction| snake.is_turn_allowed(snake.direction, *direction))
+            .filter_map(|direction| game.neighbor_index(head, direction).map(|index| (direction, index)))
+            .collect();
+
+        let body = snake.body.clone();
+        let free_after = free_after(game, self.player, &body);
+        let enterable = |index: usize| free_after[index] <= 1;
+
+        // nearest food, if the way there does not leave the snake trapped
+        if let Some(path) = shortest_path(game, &free_after, head, |index| game.field[index].cell_type == CellType::Food) {
+            let mut after_meal = body.clone();
+            for (i, index) in path.iter().enumerate() {
+                after_meal.push_front(*index);
+                if i + 1 < path.len() {
+                    after_meal.pop_back();
+                }
+            }
+
+            if after_meal.len() == game.field.len() || can_reach_tail(game, self.player, &after_meal) {
+                if let Some((direction, _)) = moves.iter().find(|(_, index)| *index == path[0]) {
+                    return Some(*direction);
+                }
+            }
+        }
+
+        // moves after which the tail is still in reach, with the distance to it
+        let safe_moves: Vec<(Direction, usize)> = moves
+            .iter()
+            .filter(|(_, index)| enterable(*index))
+            .filter_map(|(direction, index)| {
+                let mut moved = body.clone();
+                moved.push_front(*index);
+                if game.field[*index].cell_type != CellType::Food {
+                    moved.pop_back();
+                }
+
+                tail_distance(game, self.player, &moved).map(|distance| (*direction, distance))
+            })
+            .collect();
+
+        // going round in circles, a different way around may bring the food within reach
+        if self.idle_steps > game.field.len() / 4 && !safe_moves.is_empty() {
+            return Some(safe_moves[self.rng.gen_range(0..safe_moves.len())].0);
+        }
+
+        // follow the tail, the longest way around keeps the most room for later
+        let to_tail = safe_moves.iter().max_by_key(|(_, distance)| *distance);
+
+        if let Some((direction, _)) = to_tail {
+            return Some(*direction);
+        }
+
+        // trapped, stay alive for as long as possible
+        moves
+            .iter()
+            .filter(|(_, index)| enterable(*index))
+            .max_by_key(|(_, index)| reachable_area(game, &free_after, *index))
+            .map(|(direction, _)| *direction)
+    }
+}
+
+/// Steps until the head may enter each cell: 0 for free cells, `usize::MAX` for borders and
+/// other snakes. A segment of the snake of `player` is left once the tail has moved past it,
+/// the tail itself in the next step unless the snake is too short to turn into it.
+fn free_after(game: &Game, player: usize, body: &VecDeque<usize>) -> Vec<usize> {
+    let mut free_after: Vec<usize> = game.field
+        .iter()
+        .map(|cell| match cell.cell_type {
+            CellType::Border => usize::MAX,
+            CellType::Snake(owner, _) if owner != player => usize::MAX,
+            _ => 0,
+        })
+        .collect();
+
+    for (steps, index) in body.iter().rev().enumerate() {
+        free_after[*index] = steps + 1;
+    }
+
+    if body.len() <= 2 {
+        if let Some(tail) = body.back() {
+            free_after[*tail] = 2;
+        }
+    }
+
+    free_after
+}
+
+/// Breadth-first search from `start` to the closest cell matching `is_goal`, entering body
+/// cells only once they are left. The tail stays put while the snake eats, and every meal
+/// may drop new food on the way, so each one (starting on food counts) costs a step of margin.
+/// Goal cells may be blocked. Returns the path without `start`.
+fn shortest_path(
+    game: &Game,
+    free_after: &[usize],
+    start: usize,
+    is_goal: impl Fn(usize) -> bool,
+) -> Option<Vec<usize>> {
+    let mut came_from: Vec<Option<usize>> = vec![None; free_after.len()];
+    // steps the tail is sure to have moved when the head gets to a cell
+    let mut tail_moves = vec![0i64; free_after.len()];
+    tail_moves[start] = -i64::from(game.field[start].cell_type == CellType::Food);
+    let mut queue = VecDeque::from([start]);
+    came_from[start] = Some(start);
+
+    while let Some(index) = queue.pop_front() {
+        for direction in DIRECTIONS {
+            let next = match game.neighbor_index(index, direction) {
+                Some(next) => next,
+                None => continue,
+            };
+
+            if came_from[next].is_some() {
+                continue;
+            }
+
+            if is_goal(next) {
+                let mut path = vec![next];
+                let mut current = index;
+                while current != start {
+                    path.push(current);
+                    current = came_from[current]?;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let moves = match game.field[next].cell_type {
+                CellType::Food => tail_moves[index] - 1,
+                _ => tail_moves[index] + 1,
+            };
+
+            if free_after[next] != usize::MAX && free_after[next] as i64 <= moves {
+                came_from[next] = Some(index);
+                tail_moves[next] = moves;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Number of steps from the head of `body` to its tail, if there is a way.
+fn tail_distance(game: &Game, player: usize, body: &VecDeque<usize>) -> Option<usize> {
+    let (head, tail) = (*body.front()?, *body.back()?);
+    if body.len() == 1 {
+        return Some(0);
+    }
+
+    let free_after = free_after(game, player, body);
+    shortest_path(game, &free_after, head, |index| index == tail).map(|path| path.len())
+}
+
+fn can_reach_tail(game: &Game, player: usize, body: &VecDeque<usize>) -> bool {
+    tail_distance(game, player, body).is_some()
+}
+
+/// Number of free cells connected to `start`.
+fn reachable_area(game: &Game, free_after: &[usize], start: usize) -> usize {
+    let mut visited = vec![false; free_after.len()];
+    let mut queue = VecDeque::from([start]);
+    visited[start] = true;
+    let mut area = 0;
+
+    while let Some(index) = queue.pop_front() {
+        area += 1;
+
+        for direction in DIRECTIONS {
+            if let Some(next) = game.neighbor_index(index, direction) {
+                if !visited[next] && free_after[next] == 0 {
+                    visited[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    area
+}
+
+impl Controller for Autopilot {
+    fn next_direction(&mut self, game: &Game) -> Option<Direction> {
+        Autopilot::next_direction(self, game)
+    }
+
+    fn player(&self) -> usize {
+        self.player
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::{BoardTopology, SnakeBodyPart, StepOutcome};
+
+    /// Whether moving the snake of `player` to `direction` ends in a wall or a snake body.
+    /// The tail of a longer snake moves on in the same step, so it is fine to enter.
+    fn is_fatal(game: &Game, player: usize, direction: Direction) -> bool {
+        let snake = &game.snakes[player];
+        let index = match game.neighbor_index(snake.head_index, direction) {
+            Some(index) => index,
+            None => return true,
+        };
+
+        match game.field[index].cell_type {
+            CellType::Border => true,
+            CellType::Snake(owner, _) => owner != player || snake.len() <= 2 || snake.body.back() != Some(&index),
+            _ => false,
+        }
+    }
+
+    /// Plays the round with one autopilot per snake, checking every move before it is made.
+    fn play_checked(game: &mut Game, max_steps: usize) {
+        let mut autopilots: Vec<Autopilot> = (0..game.snakes.len()).map(Autopilot::for_player).collect();
+        game.resume();
+
+        while game.step_count < max_steps {
+            for autopilot in autopilots.iter_mut() {
+                if let Some(direction) = autopilot.next_direction(game) {
+                    let snake = &game.snakes[autopilot.player];
+                    assert!(snake.is_turn_allowed(snake.direction, direction), "reversal at step {}", game.step_count);
+                    assert!(!is_fatal(game, autopilot.player, direction), "fatal move at step {}", game.step_count);
+                    game.set_player_direction(autopilot.player, direction);
+                }
+            }
+
+            if let StepOutcome::RoundOver(_) = game.step(None) {
+                return;
+            }
+        }
+    }
+
+    /// 8x8 walled game with the snake lying on `body`, head first, heading to `direction`.
+    fn game_with_snake(body: &[(i32, i32)], direction: Direction) -> Game {
+        let mut game = Game::new(8, 8, BoardTopology::Walled, 1).unwrap();
+        for index in game.snakes[0].body.clone() {
+            game.field[index].cell_type = CellType::Empty;
+        }
+
+        let body: VecDeque<usize> = body.iter().map(|(x, y)| game.get_field_index(*x, *y)).collect();
+        for index in body.iter() {
+            game.field[*index].cell_type = CellType::Snake(0, SnakeBodyPart::Body(0));
+        }
+
+        let snake = &mut game.snakes[0];
+        snake.head_index = body[0];
+        snake.body = body;
+        snake.direction = direction;
+        game
+    }
+
+    #[test]
+    fn turns_away_from_the_corner() {
+        let game = game_with_snake(&[(1, 1), (2, 1), (3, 1)], Direction::Left);
+
+        assert_eq!(Autopilot::new().next_direction(&game), Some(Direction::Down));
+    }
+
+    #[test]
+    fn turns_away_from_its_body() {
+        let body = [(2, 2), (3, 2), (3, 1), (2, 1), (1, 1), (1, 2), (1, 3)];
+        let game = game_with_snake(&body, Direction::Left);
+
+        assert_eq!(Autopilot::new().next_direction(&game), Some(Direction::Down));
+    }
+
+    #[test]
+    fn never_moves_into_a_wall_or_a_body() {
+        for seed in 0..8 {
+            for topology in [BoardTopology::Walled, BoardTopology::Wrap] {
+                let mut game = Game::new(10, 10, topology, seed).unwrap();
+                play_checked(&mut game, 3000);
+            }
+
+            let mut game = Game::from_level(Level::bundled("rooms").unwrap(), seed).unwrap();
+            play_checked(&mut game, 3000);
+
+            let mut game = Game::with_players(12, 12, BoardTopology::Walled, 2, seed).unwrap();
+            play_checked(&mut game, 3000);
+        }
+    }
+}
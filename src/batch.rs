@@ -0,0 +1,675 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use rand::{Rng, RngCore};
+
+use crate::error::SnakeError;
+use crate::point_2d::Point2D;
+use crate::rng::GameRng;
+use crate::score::{RoundStats, Score, ScoreRules};
+use crate::{BoardTopology, CellType, Direction, Game, GameOverType, GameState, StepOutcome, MIN_FIELD_SIZE};
+
+/// Content of a field cell in a `GameBatch`, one byte instead of a full `Cell`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BatchCell {
+    Empty,
+    Border,
+    Snake,
+    Food,
+}
+
+/// Many single-player games on boards of the same size, stepped together.
+///
+/// The games follow the rules of `Game::new` with the default food placement and score rules,
+/// but their state is kept in parallel arrays indexed by game: fields, heads, bodies, directions,
+/// generators, scores and outcomes. The fields and bodies of all games are laid out back to back
+/// in one vector each, so stepping walks flat memory instead of one `Game` after the other.
+///
+/// With several threads the games are split into contiguous lanes, each holding the arrays of its
+/// games and owned by a worker started with the batch. Every step hands each worker its lane and
+/// waits for it to come back. Finished rounds are reported once through their
+/// `StepOutcome::RoundOver` and, with `auto_reset`, replaced by a new round right away.
+#[derive(Debug)]
+pub struct GameBatch {
+    pub auto_reset: bool,
+    board: Board,
+    lanes: Vec<Lane>,
+    lane_size: usize,       // games per lane, all but the last lane are full
+    workers: Vec<Worker>,   // one per lane, none when stepping on the calling thread
+    outcomes: Vec<StepOutcome>,     // outcome of the last step of every game
+    rounds_finished: Vec<usize>,
+    total_points: Vec<u64>,         // points scored in all finished rounds
+}
+
+/// Field shape shared by all games of a batch.
+#[derive(Debug, Copy, Clone)]
+struct Board {
+    cols: usize,
+    rows: usize,
+    topology: BoardTopology,
+    walls: usize,   // border cells of every field
+}
+
+/// State of a contiguous range of games, one entry per game unless noted otherwise.
+#[derive(Debug, Default)]
+struct Lane {
+    fields: Vec<BatchCell>,     // cells of every game, field after field
+    bodies: Vec<usize>,         // a ring of field indices per game, as long as its field
+    body_starts: Vec<usize>,    // position of the head in the ring of a game
+    lengths: Vec<usize>,
+    heads: Vec<usize>,
+    directions: Vec<Direction>,
+    states: Vec<GameState>,
+    step_counts: Vec<usize>,
+    scores: Vec<Score>,
+    stats: Vec<RoundStats>,
+    rounds: Vec<usize>,
+    seeds: Vec<u64>,
+    rngs: Vec<GameRng>,
+    actions: Vec<Option<Direction>>,
+    outcomes: Vec<StepOutcome>,
+    auto_reset: bool,
+}
+
+/// Thread stepping the lanes sent to it, until the batch is dropped.
+#[derive(Debug)]
+struct Worker {
+    lanes: Option<Sender<(Board, Lane)>>,  // dropped first to let the thread end
+    done: Receiver<Lane>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl GameBatch {
+    /// `count` games, each seeded from a generator started with `seed`,
+    /// stepped on as many threads as the machine has cores.
+    pub fn new(count: usize, cols: usize, rows: usize, topology: BoardTopology, seed: u64) -> Result<GameBatch, SnakeError> {
+        if cols < MIN_FIELD_SIZE || rows < MIN_FIELD_SIZE {
+            return Err(SnakeError::BoardTooSmall {
+                cols,
+                rows,
+                min_cols: MIN_FIELD_SIZE,
+                min_rows: MIN_FIELD_SIZE,
+            });
+        }
+
+        let walls = match topology {
+            BoardTopology::Walled => 2 * (cols + rows) - 4,
+            BoardTopology::Wrap => 0,
+        };
+        let board = Board { cols, rows, topology, walls };
+
+        let mut seeds = GameRng::new(seed);
+        let mut lane = Lane::default();
+        for _ in 0..count {
+            lane.push(&board, seeds.next_u64());
+        }
+
+        let mut batch = GameBatch {
+            auto_reset: true,
+            board,
+            lanes: vec![lane],
+            lane_size: count.max(1),
+            workers: Vec::new(),
+            outcomes: vec![StepOutcome::Idle; count],
+            rounds_finished: vec![0; count],
+            total_points: vec![0; count],
+        };
+        batch.set_threads(thread::available_parallelism().map_or(1, |threads| threads.get()));
+        Ok(batch)
+    }
+
+    /// Number of threads stepping the games, 1 steps them on the calling thread.
+    pub fn threads(&self) -> usize {
+        self.workers.len().max(1)
+    }
+
+    /// Splits the games into `threads` lanes and starts a worker for each.
+    /// The previous workers are stopped, so call this before stepping rather than between steps.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.workers.clear();
+
+        let mut lane = Lane::default();
+        for other in self.lanes.drain(..) {
+            lane.append(other);
+        }
+
+        let threads = threads.clamp(1, lane.len().max(1));
+        self.lane_size = lane.len().div_ceil(threads).max(1);
+
+        while lane.len() > self.lane_size {
+            let rest = lane.split_off(&self.board, self.lane_size);
+            self.lanes.push(lane);
+            lane = rest;
+        }
+        self.lanes.push(lane);
+
+        if self.lanes.len() > 1 {
+            self.workers = self.lanes.iter().map(|_| Worker::spawn()).collect();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    /// Lane holding game `index` and the position of the game in it.
+    fn locate(&self, index: usize) -> Option<(&Lane, usize)> {
+        let lane = self.lanes.get(index / self.lane_size)?;
+        let game = index % self.lane_size;
+        (game < lane.len()).then_some((lane, game))
+    }
+
+    /// Cells of the field of game `index`, row by row.
+    pub fn field(&self, index: usize) -> Option<&[BatchCell]> {
+        let cells = self.board.cols * self.board.rows;
+        self.locate(index).map(|(lane, game)| &lane.fields[game * cells..(game + 1) * cells])
+    }
+
+    /// Field index of the snake head of every game.
+    pub fn heads(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lanes.iter().flat_map(|lane| lane.heads.iter().copied())
+    }
+
+    /// Snake length of every game.
+    pub fn lengths(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lanes.iter().flat_map(|lane| lane.lengths.iter().copied())
+    }
+
+    /// Game `index` as a stand-alone `Game`, e.g. to render or save it.
+    pub fn game(&self, index: usize) -> Option<Game> {
+        let (lane, game) = self.locate(index)?;
+        let board = &self.board;
+        let cells = board.cols * board.rows;
+
+        let mut snapshot = Game::new(board.cols, board.rows, board.topology, lane.seeds[game]).ok()?;
+        for (cell, batch_cell) in snapshot.field.iter_mut().zip(&lane.fields[game * cells..(game + 1) * cells]) {
+            cell.cell_type = match batch_cell {
+                BatchCell::Empty => CellType::Empty,
+                BatchCell::Border => CellType::Border,
+                BatchCell::Food => CellType::Food,
+                BatchCell::Snake => cell.cell_type, // rewritten from the body below
+            };
+        }
+
+        let snake = &mut snapshot.snakes[0];
+        snake.body = (0..lane.lengths[game]).map(|i| lane.body_segment(board, game, i)).collect::<VecDeque<usize>>();
+        snake.head_index = lane.heads[game];
+        snake.direction = lane.directions[game];
+        snake.score = lane.scores[game];
+        snake.death = match lane.states[game] {
+            GameState::GameOver(game_over_type) => Some(game_over_type),
+            _ => None,
+        };
+        snapshot.update_snake_cells();
+
+        snapshot.state = lane.states[game];
+        snapshot.step_count = lane.step_counts[game];
+        snapshot.stats = lane.stats[game];
+        snapshot.round = lane.rounds[game];
+        snapshot.rng = lane.rngs[game];
+        Some(snapshot)
+    }
+
+    pub fn outcomes(&self) -> &[StepOutcome] {
+        &self.outcomes
+    }
+
+    pub fn rounds_finished(&self) -> &[usize] {
+        &self.rounds_finished
+    }
+
+    /// Points of all finished rounds of every game.
+    pub fn total_points(&self) -> &[u64] {
+        &self.total_points
+    }
+
+    /// Plays one step of every game, with `actions[i]` as the input of game `i`.
+    /// Games without an action, or with `None`, go straight. Paused games are resumed.
+    pub fn step(&mut self, actions: &[Option<Direction>]) -> &[StepOutcome] {
+        for (i, lane) in self.lanes.iter_mut().enumerate() {
+            let start = (i * self.lane_size).min(actions.len());
+            let end = (start + lane.len()).min(actions.len());
+            lane.actions.clear();
+            lane.actions.extend_from_slice(&actions[start..end]);
+            lane.auto_reset = self.auto_reset;
+        }
+
+        if self.workers.is_empty() {
+            for lane in self.lanes.iter_mut() {
+                lane.step(&self.board);
+            }
+        } else {
+            for (lane, worker) in self.lanes.iter_mut().zip(self.workers.iter()) {
+                worker.send(self.board, std::mem::take(lane));
+            }
+            for (lane, worker) in self.lanes.iter_mut().zip(self.workers.iter()) {
+                *lane = worker.receive();
+            }
+        }
+
+        let outcomes = self.lanes.iter().flat_map(|lane| lane.outcomes.iter());
+        for (i, outcome) in outcomes.enumerate() {
+            if let StepOutcome::RoundOver(stats) = outcome {
+                self.rounds_finished[i] += 1;
+                self.total_points[i] += stats.score;
+            }
+            self.outcomes[i] = *outcome;
+        }
+
+        &self.outcomes
+    }
+
+    /// Starts a new round in every game.
+    pub fn reset(&mut self) {
+        for lane in self.lanes.iter_mut() {
+            for game in 0..lane.len() {
+                lane.new_round(&self.board, game);
+            }
+        }
+        self.outcomes.fill(StepOutcome::Idle);
+    }
+}
+
+impl Lane {
+    fn len(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// Adds a game in its first round, like `Game::new` with `seed`.
+    fn push(&mut self, board: &Board, seed: u64) {
+        let cells = board.cols * board.rows;
+
+        self.fields.resize(self.fields.len() + cells, BatchCell::Empty);
+        self.bodies.resize(self.bodies.len() + cells, 0);
+        self.body_starts.push(0);
+        self.lengths.push(0);
+        self.heads.push(0);
+        self.directions.push(Direction::Right);
+        self.states.push(GameState::Paused);
+        self.step_counts.push(0);
+        self.scores.push(Score::default());
+        self.stats.push(RoundStats::default());
+        self.rounds.push(0);
+        self.seeds.push(seed);
+        self.rngs.push(GameRng::new(seed));
+        self.outcomes.push(StepOutcome::Idle);
+
+        self.reset_round(board, self.len() - 1);
+    }
+
+    fn append(&mut self, mut other: Lane) {
+        self.fields.append(&mut other.fields);
+        self.bodies.append(&mut other.bodies);
+        self.body_starts.append(&mut other.body_starts);
+        self.lengths.append(&mut other.lengths);
+        self.heads.append(&mut other.heads);
+        self.directions.append(&mut other.directions);
+        self.states.append(&mut other.states);
+        self.step_counts.append(&mut other.step_counts);
+        self.scores.append(&mut other.scores);
+        self.stats.append(&mut other.stats);
+        self.rounds.append(&mut other.rounds);
+        self.seeds.append(&mut other.seeds);
+        self.rngs.append(&mut other.rngs);
+        self.outcomes.append(&mut other.outcomes);
+    }
+
+    /// Moves the games from `at` on into a new lane.
+    fn split_off(&mut self, board: &Board, at: usize) -> Lane {
+        let cells = board.cols * board.rows;
+
+        Lane {
+            fields: self.fields.split_off(at * cells),
+            bodies: self.bodies.split_off(at * cells),
+            body_starts: self.body_starts.split_off(at),
+            lengths: self.lengths.split_off(at),
+            heads: self.heads.split_off(at),
+            directions: self.directions.split_off(at),
+            states: self.states.split_off(at),
+            step_counts: self.step_counts.split_off(at),
+            scores: self.scores.split_off(at),
+            stats: self.stats.split_off(at),
+            rounds: self.rounds.split_off(at),
+            seeds: self.seeds.split_off(at),
+            rngs: self.rngs.split_off(at),
+            outcomes: self.outcomes.split_off(at),
+            ..Lane::default()
+        }
+    }
+
+    /// Field index of segment `i` of the snake of `game`, the head is segment 0.
+    fn body_segment(&self, board: &Board, game: usize, i: usize) -> usize {
+        let cells = board.cols * board.rows;
+        self.bodies[game * cells + (self.body_starts[game] + i) % cells]
+    }
+
+    fn step(&mut self, board: &Board) {
+        for game in 0..self.len() {
+            if self.states[game] == GameState::Paused {
+                self.states[game] = GameState::Playing;
+            }
+
+            let action = self.actions.get(game).copied().flatten();
+            let outcome = self.advance(board, game, action);
+
+            if let StepOutcome::RoundOver(_) = outcome {
+                if self.auto_reset {
+                    self.new_round(board, game);
+                }
+            }
+
+            self.outcomes[game] = outcome;
+        }
+    }
+
+    /// One step of `game`, following `Game::step` for a single snake.
+    fn advance(&mut self, board: &Board, game: usize, action: Option<Direction>) -> StepOutcome {
+        if self.states[game] != GameState::Playing {
+            return StepOutcome::Idle;
+        }
+
+        // one input per step, so a turn is taken right away instead of being queued
+        if let Some(direction) = action {
+            let current = self.directions[game];
+            if self.lengths[game] <= 1 || direction != current.opposite() {
+                self.directions[game] = direction;
+            }
+        }
+
+        let cells = board.cols * board.rows;
+        let field = game * cells;
+        let head = self.heads[game];
+        let size = Point2D::new(board.cols as i32, board.rows as i32);
+        let head_pos = Point2D::new((head % board.cols) as i32, (head / board.cols) as i32);
+
+        let new_head = match board.topology.wrap(head_pos + self.directions[game].offset(), size) {
+            Some(pos) => pos.y as usize * board.cols + pos.x as usize,
+            None => return self.end_round(game, GameOverType::BorderHit),
+        };
+
+        let length = self.lengths[game];
+        let tail = self.body_segment(board, game, length - 1);
+        let grows = self.fields[field + new_head] == BatchCell::Food;
+
+        match self.fields[field + new_head] {
+            BatchCell::Border => return self.end_round(game, GameOverType::BorderHit),
+            // the tail leaves its cell in this step, the head may enter it
+            BatchCell::Snake if new_head != tail => return self.end_round(game, GameOverType::SelfBite),
+            _ => {},
+        }
+
+        if !grows {
+            self.fields[field + tail] = BatchCell::Empty;
+            self.lengths[game] -= 1;
+        }
+
+        self.body_starts[game] = (self.body_starts[game] + cells - 1) % cells;
+        self.bodies[field + self.body_starts[game]] = new_head;
+        self.lengths[game] += 1;
+        self.heads[game] = new_head;
+        self.fields[field + new_head] = BatchCell::Snake;
+
+        self.step_counts[game] += 1;
+
+        if grows {
+            self.scores[game].record_meal(&ScoreRules::default(), self.step_counts[game]);
+            self.stats[game].food_eaten += 1;
+            self.spawn_food(board, game);
+        }
+
+        let stats = &mut self.stats[game];
+        stats.max_length = stats.max_length.max(self.lengths[game]);
+
+        // food cells count as free, so the field is full once walls and snake cover it
+        if board.walls + self.lengths[game] >= cells {
+            return self.end_round(game, GameOverType::PlaygroundFilled);
+        }
+
+        if grows {
+            StepOutcome::Ate
+        } else {
+            StepOutcome::Moved
+        }
+    }
+
+    fn end_round(&mut self, game: usize, game_over_type: GameOverType) -> StepOutcome {
+        self.states[game] = GameState::GameOver(game_over_type);
+
+        let stats = &mut self.stats[game];
+        stats.score = self.scores[game].points;
+        stats.steps = self.step_counts[game];
+        stats.game_over = Some(game_over_type);
+        StepOutcome::RoundOver(*stats)
+    }
+
+    /// Puts food on a random empty cell unless the field has some, like `UniformRandom`.
+    fn spawn_food(&mut self, board: &Board, game: usize) {
+        let cells = board.cols * board.rows;
+        let field = &mut self.fields[game * cells..(game + 1) * cells];
+
+        if field.contains(&BatchCell::Food) {
+            return;
+        }
+
+        let free_cells = field.iter().filter(|cell| **cell == BatchCell::Empty).count();
+        if free_cells == 0 {
+            return;
+        }
+
+        let chosen = self.rngs[game].gen_range(0..free_cells);
+        if let Some(cell) = field.iter_mut().filter(|cell| **cell == BatchCell::Empty).nth(chosen) {
+            *cell = BatchCell::Food;
+        }
+    }
+
+    /// Starts a fresh round of `game` seeded from the generator of the previous one.
+    fn new_round(&mut self, board: &Board, game: usize) {
+        self.seeds[game] = self.rngs[game].next_u64();
+        self.rngs[game] = GameRng::new(self.seeds[game]);
+        self.rounds[game] += 1;
+        self.reset_round(board, game);
+    }
+
+    fn reset_round(&mut self, board: &Board, game: usize) {
+        let (cols, rows) = (board.cols, board.rows);
+        let cells = cols * rows;
+        let walled = board.topology == BoardTopology::Walled;
+
+        for (index, cell) in self.fields[game * cells..(game + 1) * cells].iter_mut().enumerate() {
+            let (x, y) = (index % cols, index / cols);
+            let on_edge = x == 0 || y == 0 || x == cols - 1 || y == rows - 1;
+            *cell = if walled && on_edge { BatchCell::Border } else { BatchCell::Empty };
+        }
+
+        // in the middle of the field, like the only snake of `Game::new`
+        let head = rows / 2 * cols + cols / 2;
+        self.fields[game * cells + head] = BatchCell::Snake;
+        self.bodies[game * cells] = head;
+        self.body_starts[game] = 0;
+        self.lengths[game] = 1;
+        self.heads[game] = head;
+        self.directions[game] = Direction::Right;
+
+        self.states[game] = GameState::Paused;
+        self.step_counts[game] = 0;
+        self.scores[game] = Score::default();
+        self.stats[game] = RoundStats {
+            max_length: 1,
+            ..RoundStats::default()
+        };
+
+        self.spawn_food(board, game);
+    }
+}
+
+impl Worker {
+    fn spawn() -> Worker {
+        let (lanes, jobs) = mpsc::channel::<(Board, Lane)>();
+        let (results, done) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            for (board, mut lane) in jobs {
+                lane.step(&board);
+                if results.send(lane).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Worker { lanes: Some(lanes), done, handle: Some(handle) }
+    }
+
+    fn send(&self, board: Board, lane: Lane) {
+        self.lanes
+            .as_ref()
+            .and_then(|lanes| lanes.send((board, lane)).ok())
+            .expect("batch worker stopped");
+    }
+
+    fn receive(&self) -> Lane {
+        self.done.recv().expect("batch worker stopped")
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.lanes = None;
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+    /// Random input for `count` games.
+    fn random_actions(input_rng: &mut GameRng, count: usize) -> Vec<Option<Direction>> {
+        (0..count).map(|_| Some(DIRECTIONS[(input_rng.next_u32() % 4) as usize])).collect()
+    }
+
+    /// Plays `steps` steps of random input, returns the final head of every game.
+    fn play(batch: &mut GameBatch, steps: usize) -> Vec<usize> {
+        let mut input_rng = GameRng::new(7);
+
+        for _ in 0..steps {
+            let actions = random_actions(&mut input_rng, batch.len());
+            batch.step(&actions);
+        }
+
+        batch.heads().collect()
+    }
+
+    #[test]
+    fn games_follow_the_rules_of_game() {
+        for topology in [BoardTopology::Walled, BoardTopology::Wrap] {
+            let mut batch = GameBatch::new(12, 7, 6, topology, 11).unwrap();
+            batch.set_threads(3);
+
+            let mut seeds = GameRng::new(11);
+            let mut games: Vec<Game> = (0..12)
+                .map(|_| Game::new(7, 6, topology, seeds.next_u64()).unwrap())
+                .collect();
+
+            let mut input_rng = GameRng::new(5);
+            for _ in 0..400 {
+                let actions = random_actions(&mut input_rng, games.len());
+                let outcomes = batch.step(&actions).to_vec();
+
+                for (i, game) in games.iter_mut().enumerate() {
+                    game.resume();
+                    let outcome = game.step(actions[i]);
+                    if let StepOutcome::RoundOver(_) = outcome {
+                        game.new_round();
+                    }
+                    assert_eq!(format!("{:?}", outcomes[i]), format!("{:?}", outcome));
+
+                    let snapshot = batch.game(i).unwrap();
+                    assert_eq!(snapshot.snake().body, game.snake().body);
+                    assert_eq!(snapshot.rng, game.rng);
+                    assert_eq!((snapshot.state, snapshot.step_count, snapshot.round), (game.state, game.step_count, game.round));
+                    assert!(snapshot.field.iter().zip(game.field.iter()).all(|(a, b)| a.cell_type == b.cell_type));
+                }
+            }
+
+            assert!(batch.rounds_finished().iter().sum::<usize>() > 12);
+            assert!(batch.total_points().iter().sum::<u64>() > 0);
+        }
+    }
+
+    #[test]
+    fn threads_do_not_change_the_games() {
+        let mut single = GameBatch::new(23, 10, 10, BoardTopology::Walled, 3).unwrap();
+        single.set_threads(1);
+        let mut multi = GameBatch::new(23, 10, 10, BoardTopology::Walled, 3).unwrap();
+        multi.set_threads(4);
+        assert_eq!(multi.threads(), 4);
+
+        assert_eq!(play(&mut single, 200), play(&mut multi, 200));
+        assert_eq!(single.rounds_finished(), multi.rounds_finished());
+        assert_eq!(single.total_points(), multi.total_points());
+        assert!(single.rounds_finished().iter().sum::<usize>() > 0);
+
+        // regrouping the games keeps them as they are
+        multi.set_threads(2);
+        assert_eq!(play(&mut single, 50), play(&mut multi, 50));
+        assert_eq!(single.lengths().collect::<Vec<_>>(), multi.lengths().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn games_are_found_by_index() {
+        let mut batch = GameBatch::new(10, 10, 10, BoardTopology::Walled, 3).unwrap();
+        batch.set_threads(3);
+        let heads = play(&mut batch, 20);
+
+        for (i, head) in heads.iter().enumerate() {
+            assert_eq!(batch.game(i).unwrap().snake().head_index, *head);
+            assert_eq!(batch.field(i).unwrap()[*head], BatchCell::Snake);
+        }
+        assert!(batch.game(10).is_none());
+        assert!(batch.field(10).is_none());
+    }
+
+    #[test]
+    fn finished_rounds_start_over() {
+        let mut batch = GameBatch::new(2, 10, 10, BoardTopology::Walled, 0).unwrap();
+        batch.set_threads(2);
+
+        // straight into the wall four steps away, and half way there again
+        let finished = (0..6)
+            .map(|_| batch.step(&[]).iter().filter(|outcome| matches!(outcome, StepOutcome::RoundOver(_))).count())
+            .sum::<usize>();
+
+        assert_eq!(finished, 2);
+        assert_eq!(batch.rounds_finished(), [1, 1]);
+        assert!((0..2).all(|i| batch.game(i).unwrap().state == GameState::Playing));
+    }
+
+    #[test]
+    fn finished_rounds_wait_without_auto_reset() {
+        let mut batch = GameBatch::new(1, 10, 10, BoardTopology::Walled, 0).unwrap();
+        batch.auto_reset = false;
+
+        for _ in 0..6 {
+            batch.step(&[]);
+        }
+
+        assert!(matches!(batch.outcomes()[0], StepOutcome::Idle));
+        assert_eq!(batch.game(0).unwrap().state, GameState::GameOver(GameOverType::BorderHit));
+        assert_eq!(batch.rounds_finished(), [1]);
+
+        batch.reset();
+        assert_eq!(batch.game(0).unwrap().state, GameState::Paused);
+        assert_eq!(batch.game(0).unwrap().round, 1);
+    }
+}
@@ -0,0 +1,169 @@
+//! Terminal front-end, playable over ssh.
+//!
+//! Arrows steer (the first one starts the round), Space pauses, R starts a new round,
+//! Q or Esc quits. Pass `--wrap` for the wrap-around board.
+
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use my_snake::*;
+use my_snake::controller::KeyboardController;
+use my_snake::ticker::Ticker;
+
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+/// Puts the terminal into raw mode on the alternate screen and restores it when dropped,
+/// also when the game panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new(out: &mut Stdout) -> io::Result<TerminalGuard> {
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Head, body and tail colors of every player's snake.
+const SNAKE_COLORS: [[Color; 3]; 2] = [
+    [Color::Green, Color::Yellow, Color::DarkGreen],
+    [Color::Cyan, Color::Blue, Color::DarkCyan],
+];
+
+fn cell_style(cell_type: CellType) -> (Color, &'static str) {
+    match cell_type {
+        CellType::Uninitialized => (Color::Magenta, "??"),
+        CellType::Empty => (Color::DarkGrey, "  "),
+        CellType::Border => (Color::DarkYellow, "██"),
+        CellType::Snake(owner, snake_body_part) => {
+            let [head, body, tail] = SNAKE_COLORS[owner % SNAKE_COLORS.len()];
+            match snake_body_part {
+                SnakeBodyPart::Head(_) => (head, "██"),
+                SnakeBodyPart::Body(_) => (body, "▓▓"),
+                SnakeBodyPart::Tail(_) => (tail, "▒▒"),
+            }
+        },
+        CellType::Food => (Color::Red, "()"),
+    }
+}
+
+fn render(out: &mut Stdout, game: &Game) -> io::Result<()> {
+    queue!(out, MoveTo(0, 0))?;
+
+    for row in game.field.rows() {
+        for cell in row {
+            let (color, symbol) = cell_style(cell.cell_type);
+            queue!(out, SetForegroundColor(color), Print(symbol))?;
+        }
+        queue!(out, ResetColor, Clear(ClearType::UntilNewLine), Print("\r\n"))?;
+    }
+
+    let state = match game.state {
+        GameState::Paused => "paused - arrows to play".to_string(),
+        GameState::Playing => "playing".to_string(),
+        GameState::GameOver(game_over_type) => format!("game over: {:?} - R for a new round", game_over_type),
+    };
+
+    queue!(
+        out,
+        Print(format!("score: {}  length: {}  {}", game.snake().score.points, game.snake().len(), state)),
+        Clear(ClearType::UntilNewLine),
+        Print("\r\n"),
+        Print("arrows: steer  space: pause  r: new round  q: quit"),
+        Clear(ClearType::UntilNewLine),
+    )?;
+
+    out.flush()
+}
+
+fn main() -> io::Result<()> {
+    let (cols, rows) = (20, 15);
+    let topology = if std::env::args().any(|arg| arg == "--wrap") {
+        BoardTopology::Wrap
+    } else {
+        BoardTopology::Walled
+    };
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let mut game = match Game::new(cols, rows, topology, seed) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Failed to initialize game: {}", e);
+            std::process::exit(1);
+        },
+    };
+
+    let mut out = io::stdout();
+    let guard = TerminalGuard::new(&mut out)?;
+    execute!(out, Clear(ClearType::All))?;
+
+    let mut ticker = Ticker::new(&game);
+    let mut keyboard = KeyboardController::new();
+    let mut last_frame = Instant::now();
+    let mut last_stats = None;
+
+    loop {
+        if event::poll(FRAME_TIME)? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                let direction = match key_event.code {
+                    KeyCode::Up => Some(Direction::Up),
+                    KeyCode::Down => Some(Direction::Down),
+                    KeyCode::Left => Some(Direction::Left),
+                    KeyCode::Right => Some(Direction::Right),
+                    _ => None,
+                };
+
+                match (key_event.code, direction, game.state) {
+                    (_, Some(direction), GameState::Paused) => game.play(direction),
+                    (_, Some(direction), _) => keyboard.press(direction),
+                    (KeyCode::Char(' '), _, _) => game.toggle_pause(),
+                    (KeyCode::Char('r') | KeyCode::Char('R'), _, _) => {
+                        game.new_round();
+                        ticker.reset(&game);
+                        keyboard.clear();
+                    },
+                    (KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc, _, _) => break,
+                    _ => {},
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let dt = now.duration_since(last_frame).as_secs_f64();
+        last_frame = now;
+
+        if let Some(stats) = ticker.update_controlled(dt, &mut game, &mut keyboard) {
+            last_stats = Some(stats);
+        }
+
+        render(&mut out, &game)?;
+    }
+
+    drop(guard);
+
+    if let Some(stats) = last_stats {
+        println!("Round over: {:?}", stats);
+    }
+
+    Ok(())
+}
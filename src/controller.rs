@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use crate::error::SnakeError;
+use crate::replay::direction_from_code;
+use crate::score::RoundStats;
+use crate::{Direction, Game, StepOutcome, INPUT_QUEUE_CAPACITY};
+
+/// Source of input for a game: a player at the keyboard, a script or a computer player.
+pub trait Controller {
+    /// Called right before every step with the game as it is.
+    /// Returns the direction to turn to in that step, `None` to keep going straight.
+    fn next_direction(&mut self, game: &Game) -> Option<Direction>;
+
+    /// Index of the snake in `Game::snakes` this controller steers.
+    fn player(&self) -> usize {
+        0
+    }
+}
+
+/// Key presses collected between two steps, handed to the game one per step.
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardController {
+    pub player: usize,
+    pressed: VecDeque<Direction>,
+}
+
+impl KeyboardController {
+    /// Keyboard of player one.
+    pub fn new() -> KeyboardController {
+        KeyboardController::default()
+    }
+
+    pub fn for_player(player: usize) -> KeyboardController {
+        KeyboardController { player, pressed: VecDeque::new() }
+    }
+
+    /// Remembers a key press, the oldest presses are kept when keys come faster than steps.
+    pub fn press(&mut self, direction: Direction) {
+        if self.pressed.back() != Some(&direction) && self.pressed.len() < INPUT_QUEUE_CAPACITY {
+            self.pressed.push_back(direction);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pressed.clear();
+    }
+}
+
+impl Controller for KeyboardController {
+    fn next_direction(&mut self, game: &Game) -> Option<Direction> {
+        let snake = game.snakes.get(self.player)?;
+
+        // presses the game would ignore do not use up a step
+        while let Some(direction) = self.pressed.pop_front() {
+            if direction != snake.direction && snake.is_turn_allowed(snake.direction, direction) {
+                return Some(direction);
+            }
+        }
+
+        None
+    }
+
+    fn player(&self) -> usize {
+        self.player
+    }
+}
+
+/// Plays a fixed list of inputs, one per step, and goes straight once the list runs out.
+///
+/// Script files hold whitespace separated tokens, one per step: `L`, `R`, `U` or `D` turns,
+/// `.` goes straight. A token may start with a repeat count, `4.` is four steps straight.
+/// Repeated inputs are stored once with their count, so large counts cost no memory.
+/// Everything after `;` on a line is a comment.
+/// ```text
+/// ; around the block
+/// 3. D 3. L 3. U
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptedController {
+    runs: Vec<(Option<Direction>, usize)>,  // input and the number of steps it is repeated for
+    next: usize,        // index into `runs`
+    repeated: usize,    // steps already played of the next run
+}
+
+impl ScriptedController {
+    pub fn new(inputs: Vec<Option<Direction>>) -> ScriptedController {
+        let mut script = ScriptedController::default();
+        for input in inputs {
+            script.push(input, 1);
+        }
+        script
+    }
+
+    /// Appends `input` repeated for `count` steps.
+    fn push(&mut self, input: Option<Direction>, count: usize) {
+        match self.runs.last_mut() {
+            _ if count == 0 => {},
+            Some((last, last_count)) if *last == input => *last_count = last_count.saturating_add(count),
+            _ => self.runs.push((input, count)),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.runs.len()
+    }
+
+    /// Starts the script over.
+    pub fn rewind(&mut self) {
+        self.next = 0;
+        self.repeated = 0;
+    }
+}
+
+impl Controller for ScriptedController {
+    fn next_direction(&mut self, _game: &Game) -> Option<Direction> {
+        let (input, count) = *self.runs.get(self.next)?;
+        self.repeated += 1;
+        if self.repeated >= count {
+            self.next += 1;
+            self.repeated = 0;
+        }
+        input
+    }
+}
+
+impl FromStr for ScriptedController {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut script = ScriptedController::default();
+
+        for (line_index, line) in s.lines().enumerate() {
+            let error = |message: String| SnakeError::ScriptParse { line: line_index + 1, message };
+            let line = line.split(';').next().unwrap_or_default();
+
+            for token in line.split_whitespace() {
+                let code_start = token.char_indices().last().map_or(0, |(i, _)| i);
+                let (count, code) = token.split_at(code_start);
+                let count = match count {
+                    "" => 1,
+                    count => count.parse::<usize>().map_err(|_| error(format!("invalid repeat count in `{}`", token)))?,
+                };
+
+                let input = match code {
+                    "." => None,
+                    _ => Some(code.chars().next().and_then(direction_from_code).ok_or_else(|| {
+                        error(format!("`{}` must end with one of L, R, U, D or .", token))
+                    })?),
+                };
+
+                script.push(input, count);
+            }
+        }
+
+        Ok(script)
+    }
+}
+
+/// Plays the round of `game` to its end with input from `controller`, without any timing.
+/// A paused round is resumed first. Gives up after `max_steps` steps of the round and returns
+/// `None`, computer players can go in circles forever.
+pub fn play_round(game: &mut Game, controller: &mut dyn Controller, max_steps: usize) -> Option<RoundStats> {
+    game.resume();
+
+    while game.step_count < max_steps {
+        if let Some(direction) = controller.next_direction(game) {
+            game.set_player_direction(controller.player(), direction);
+        }
+
+        match game.step(None) {
+            StepOutcome::RoundOver(stats) => return Some(stats),
+            StepOutcome::Idle => return None,
+            StepOutcome::Moved | StepOutcome::Ate => {},
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_2d::Point2D;
+    use crate::{BoardTopology, CellType, GameOverType};
+
+    #[test]
+    fn plays_the_script_into_the_wall() {
+        // from the middle of the field: three steps right, then up until the wall
+        let mut script: ScriptedController = "; turn once\n3. U".parse().unwrap();
+        let mut game = Game::new(12, 12, BoardTopology::Walled, 0).unwrap();
+        for cell in game.field.iter_mut().filter(|cell| cell.cell_type == CellType::Food) {
+            cell.cell_type = CellType::Empty;
+        }
+        let food = game.get_field_index(8, 6);
+        game.field[food].cell_type = CellType::Food;
+
+        let stats = play_round(&mut game, &mut script, 100).unwrap();
+
+        assert_eq!(stats.game_over, Some(GameOverType::BorderHit));
+        assert_eq!(stats.steps, 8);
+        assert_eq!(stats.food_eaten, 1);
+        assert_eq!(stats.max_length, 2);
+        assert_eq!(game.get_point_from_index(game.snake().head_index), Point2D::new(9, 1));
+        assert!(script.is_finished());
+    }
+
+    #[test]
+    fn repeats_inputs_lazily() {
+        let mut script: ScriptedController = "1000000000000R".parse().unwrap();
+        let game = Game::new(12, 12, BoardTopology::Walled, 0).unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(script.next_direction(&game), Some(Direction::Right));
+        }
+        assert!(!script.is_finished());
+
+        let script: ScriptedController = "2. 0L 3.".parse().unwrap();
+        assert_eq!(script, ScriptedController::new(vec![None; 5]));
+    }
+
+    #[test]
+    fn rewinds_to_the_start() {
+        let mut script: ScriptedController = "2L D".parse().unwrap();
+        let game = Game::new(12, 12, BoardTopology::Walled, 0).unwrap();
+        let inputs = [Some(Direction::Left), Some(Direction::Left), Some(Direction::Down), None];
+
+        for input in inputs {
+            assert_eq!(script.next_direction(&game), input);
+        }
+        script.rewind();
+        for input in inputs {
+            assert_eq!(script.next_direction(&game), input);
+        }
+    }
+
+    #[test]
+    fn reports_the_line_of_bad_tokens() {
+        for (text, bad_line) in [("R\n; fine\n2x", 3), ("-1R", 1), ("99999999999999999999999.", 1), ("U\n2", 2)] {
+            match text.parse::<ScriptedController>() {
+                Err(SnakeError::ScriptParse { line, .. }) => assert_eq!(line, bad_line, "{:?}", text),
+                other => panic!("{:?} parsed as {:?}", text, other),
+            }
+        }
+    }
+}
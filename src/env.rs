@@ -0,0 +1,398 @@
+use crate::error::SnakeError;
+use crate::level::Level;
+use crate::point_2d::Point2D;
+use crate::{BoardTopology, CellType, Direction, Game, GameOverType, SnakeBodyPart, StepOutcome};
+
+/// Actions of the environment, `Direction` values by index.
+pub const ACTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+/// Offsets of the rays of `ObservationEncoding::Rays`, clockwise from up.
+const RAYS: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// Flat `f32` tensor, `data` is laid out row-major in the order of `shape`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+/// How the game is turned into an `Observation`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ObservationEncoding {
+    /// The whole field, shape `[4, rows, cols]`: wall, body, head and food planes of 0 and 1.
+    Grid,
+    /// Square window centred on the head, shape `[3, 2 * radius + 1, 2 * radius + 1]`:
+    /// wall, body and food planes. Cells off a walled field count as walls.
+    Egocentric { radius: usize },
+    /// Eight rays from the head, clockwise from up, with `1 / distance` to the nearest wall,
+    /// body part and food along each (0 when there is none), followed by the current direction
+    /// one-hot in `ACTIONS` order. Shape `[28]`.
+    Rays,
+}
+
+/// Rewards given by `SnakeEnv::step`.
+#[derive(Debug, Copy, Clone)]
+pub struct RewardShaping {
+    pub food: f32,
+    pub death: f32,     // border hit or self bite
+    pub win: f32,       // the snake filled the field
+    pub step: f32,      // every step that neither ate nor ended the episode
+    pub approach: f32,  // per cell the head got closer to the nearest food, negated when it moved away
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        RewardShaping {
+            food: 1.0,
+            death: -1.0,
+            win: 10.0,
+            step: -0.01,
+            approach: 0.0,
+        }
+    }
+}
+
+/// What happened in a `SnakeEnv::step`, besides the reward.
+#[derive(Debug, Copy, Clone)]
+pub struct StepInfo {
+    pub outcome: StepOutcome,
+    pub score: u64,
+    pub length: usize,
+    pub steps: usize,
+    pub truncated: bool,    // the episode was cut off after `max_idle_steps`, the snake is still alive
+}
+
+/// Gym-style environment for training agents, one episode per round.
+///
+/// A new environment is ready to step, its first episode is played with seed 0.
+/// Every episode is played by a single snake, player one, whose view the observations show.
+/// Actions are absolute directions, a reversal is ignored and the snake goes straight on.
+#[derive(Debug)]
+pub struct SnakeEnv {
+    pub encoding: ObservationEncoding,
+    pub rewards: RewardShaping,
+    pub max_idle_steps: usize,  // steps without food before an episode is truncated
+    cols: usize,
+    rows: usize,
+    topology: BoardTopology,
+    level: Option<Level>,
+    game: Game,
+    idle_steps: usize,
+}
+
+impl SnakeEnv {
+    pub fn new(cols: usize, rows: usize, topology: BoardTopology) -> Result<SnakeEnv, SnakeError> {
+        let game = Game::new(cols, rows, topology, 0)?;
+        Ok(SnakeEnv::with_game(game, None))
+    }
+
+    /// Environment whose episodes are played on `level`.
+    pub fn from_level(level: Level) -> Result<SnakeEnv, SnakeError> {
+        let game = Game::from_level(level.clone(), 0)?;
+        Ok(SnakeEnv::with_game(game, Some(level)))
+    }
+
+    fn with_game(mut game: Game, level: Option<Level>) -> SnakeEnv {
+        game.resume();
+
+        SnakeEnv {
+            encoding: ObservationEncoding::Grid,
+            rewards: RewardShaping::default(),
+            max_idle_steps: game.field.len(),
+            cols: game.field_size.x,
+            rows: game.field_size.y,
+            topology: game.topology,
+            level,
+            game,
+            idle_steps: 0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Starts a new episode, the same seed gives the same food placement.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        let game = match &self.level {
+            Some(level) => Game::from_level(level.clone(), seed),
+            None => Game::new(self.cols, self.rows, self.topology, seed),
+        };
+
+        self.game = game.expect("board checked when the environment was created");
+        self.game.resume();
+        self.idle_steps = 0;
+
+        self.observe()
+    }
+
+    /// Plays one step. After the round is over every step is done, with no reward.
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool, StepInfo) {
+        let distance_before = self.food_distance();
+        let outcome = self.game.step(Some(action));
+
+        let reward = match outcome {
+            StepOutcome::Idle => 0.0,
+            StepOutcome::Ate => self.rewards.food,
+            StepOutcome::RoundOver(stats) => match stats.game_over {
+                Some(GameOverType::PlaygroundFilled) => self.rewards.win,
+                _ => self.rewards.death,
+            },
+            StepOutcome::Moved => {
+                let approach = match (distance_before, self.food_distance()) {
+                    (Some(before), Some(after)) => before as f32 - after as f32,
+                    _ => 0.0,
+                };
+                self.rewards.step + self.rewards.approach * approach
+            },
+        };
+
+        match outcome {
+            StepOutcome::Ate => self.idle_steps = 0,
+            StepOutcome::Moved => self.idle_steps += 1,
+            _ => {},
+        }
+
+        let truncated = self.idle_steps >= self.max_idle_steps && matches!(outcome, StepOutcome::Moved);
+        let done = truncated || !matches!(outcome, StepOutcome::Moved | StepOutcome::Ate);
+
+        let info = StepInfo {
+            outcome,
+            score: self.game.snake().score.points,
+            length: self.game.snake().len(),
+            steps: self.game.step_count,
+            truncated,
+        };
+
+        (self.observe(), reward, done, info)
+    }
+
+    /// Observation of the current state in the configured encoding.
+    pub fn observe(&self) -> Observation {
+        match self.encoding {
+            ObservationEncoding::Grid => self.observe_grid(),
+            ObservationEncoding::Egocentric { radius } => self.observe_window(radius),
+            ObservationEncoding::Rays => self.observe_rays(),
+        }
+    }
+
+    fn observe_grid(&self) -> Observation {
+        let plane = self.cols * self.rows;
+        let mut data = vec![0.0; 4 * plane];
+
+        for (index, cell) in self.game.field.iter().enumerate() {
+            let channel = match cell.cell_type {
+                CellType::Border => 0,
+                CellType::Snake(_, SnakeBodyPart::Head(_)) => 2,
+                CellType::Snake(_, _) => 1,
+                CellType::Food => 3,
+                CellType::Empty | CellType::Uninitialized => continue,
+            };
+            data[channel * plane + index] = 1.0;
+        }
+
+        Observation { shape: vec![4, self.rows, self.cols], data }
+    }
+
+    fn observe_window(&self, radius: usize) -> Observation {
+        let size = 2 * radius + 1;
+        let plane = size * size;
+        let mut data = vec![0.0; 3 * plane];
+        let head = self.game.get_point_from_index(self.game.snake().head_index);
+
+        for y in 0..size {
+            for x in 0..size {
+                let offset = Point2D::new(x as i32 - radius as i32, y as i32 - radius as i32);
+                let channel = match self.game.wrap_point(head + offset).map(|pos| self.cell_type_at(pos)) {
+                    None | Some(CellType::Border) => 0,
+                    Some(CellType::Snake(_, SnakeBodyPart::Head(_))) => continue,
+                    Some(CellType::Snake(_, _)) => 1,
+                    Some(CellType::Food) => 2,
+                    Some(_) => continue,
+                };
+                data[channel * plane + y * size + x] = 1.0;
+            }
+        }
+
+        Observation { shape: vec![3, size, size], data }
+    }
+
+    fn observe_rays(&self) -> Observation {
+        let mut data = Vec::with_capacity(RAYS.len() * 3 + ACTIONS.len());
+        let head = self.game.get_point_from_index(self.game.snake().head_index);
+        let max_distance = self.cols.max(self.rows) as i32;
+
+        for (dx, dy) in RAYS {
+            let (mut wall, mut body, mut food) = (0.0, 0.0, 0.0);
+
+            for distance in 1..=max_distance {
+                let cell_type = match self.game.wrap_point(head + Point2D::new(dx * distance, dy * distance)) {
+                    Some(pos) => self.cell_type_at(pos),
+                    None => CellType::Border,
+                };
+                let proximity = 1.0 / distance as f32;
+
+                match cell_type {
+                    CellType::Border => {
+                        wall = proximity;
+                        break;
+                    },
+                    CellType::Snake(_, SnakeBodyPart::Head(_)) => {},
+                    CellType::Snake(_, _) if body == 0.0 => body = proximity,
+                    CellType::Food if food == 0.0 => food = proximity,
+                    _ => {},
+                }
+            }
+
+            data.extend([wall, body, food]);
+        }
+
+        data.extend(ACTIONS.iter().map(|direction| (*direction == self.game.snake().direction) as u8 as f32));
+
+        Observation { shape: vec![data.len()], data }
+    }
+
+    fn cell_type_at(&self, pos: Point2D<i32>) -> CellType {
+        self.game.field[self.game.get_index_from_point(pos)].cell_type
+    }
+
+    /// Steps from the head to the nearest food, ignoring obstacles.
+    fn food_distance(&self) -> Option<usize> {
+        let head = self.game.get_point_from_index(self.game.snake().head_index);
+        let (cols, rows) = (self.cols as i32, self.rows as i32);
+
+        self.game.field
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.cell_type == CellType::Food)
+            .map(|(index, _)| {
+                let diff = self.game.get_point_from_index(index) - head;
+                let (dx, dy) = (diff.x.abs(), diff.y.abs());
+                match self.topology {
+                    BoardTopology::Walled => (dx + dy) as usize,
+                    BoardTopology::Wrap => (dx.min(cols - dx) + dy.min(rows - dy)) as usize,
+                }
+            })
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 7x6 environment with the head at (3, 3) facing right and the only food moved to `food`.
+    fn env_with_food(topology: BoardTopology, food: Point2D<i32>) -> SnakeEnv {
+        let mut env = SnakeEnv::new(7, 6, topology).unwrap();
+
+        for cell in env.game.field.iter_mut().filter(|cell| cell.cell_type == CellType::Food) {
+            cell.cell_type = CellType::Empty;
+        }
+        let index = env.game.get_index_from_point(food);
+        env.game.field[index].cell_type = CellType::Food;
+        env
+    }
+
+    fn plane(observation: &Observation, channel: usize) -> &[f32] {
+        let plane = observation.shape[1] * observation.shape[2];
+        &observation.data[channel * plane..(channel + 1) * plane]
+    }
+
+    #[test]
+    fn grid_shows_the_whole_field() {
+        let env = env_with_food(BoardTopology::Walled, Point2D::new(1, 2));
+        let observation = env.observe();
+
+        assert_eq!(observation.shape, [4, 6, 7]);
+        assert_eq!(plane(&observation, 0).iter().sum::<f32>(), 22.0);
+        assert_eq!(plane(&observation, 0)[8], 0.0);
+        assert_eq!(plane(&observation, 1).iter().sum::<f32>(), 0.0);
+        assert_eq!(plane(&observation, 2)[3 * 7 + 3], 1.0);
+        assert_eq!(plane(&observation, 2).iter().sum::<f32>(), 1.0);
+        assert_eq!(plane(&observation, 3)[2 * 7 + 1], 1.0);
+        assert_eq!(plane(&observation, 3).iter().sum::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn egocentric_window_counts_cells_off_the_field_as_walls() {
+        let mut env = env_with_food(BoardTopology::Walled, Point2D::new(4, 3));
+        env.encoding = ObservationEncoding::Egocentric { radius: 4 };
+        env.step(Direction::Right);
+        let observation = env.observe();
+
+        // the head at (4, 3) is in the middle of a 9x9 window reaching 2 cells past the right edge
+        assert_eq!(observation.shape, [3, 9, 9]);
+        assert_eq!(plane(&observation, 0).iter().sum::<f32>(), 81.0 - 20.0);
+        assert_eq!(plane(&observation, 0)[4 * 9..5 * 9], [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        assert_eq!(plane(&observation, 1)[4 * 9 + 3], 1.0);
+        assert_eq!(plane(&observation, 1).iter().sum::<f32>(), 1.0);
+        assert_eq!(plane(&observation, 2).iter().sum::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn rays_measure_distances_and_show_the_direction() {
+        let mut env = env_with_food(BoardTopology::Walled, Point2D::new(1, 3));
+        env.encoding = ObservationEncoding::Rays;
+        let observation = env.observe();
+
+        assert_eq!(observation.shape, [28]);
+        // up, right and down hit the wall after 3, 3 and 2 cells, the food is 2 cells to the left
+        assert_eq!(observation.data[0..3], [1.0 / 3.0, 0.0, 0.0]);
+        assert_eq!(observation.data[6..9], [1.0 / 3.0, 0.0, 0.0]);
+        assert_eq!(observation.data[12..15], [0.5, 0.0, 0.0]);
+        assert_eq!(observation.data[18..21], [1.0 / 3.0, 0.0, 0.5]);
+        assert_eq!(observation.data[24..], [0.0, 1.0, 0.0, 0.0]);
+
+        env.step(Direction::Up);
+        assert_eq!(env.observe().data[24..], [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn rewards_for_food_and_death() {
+        let mut env = env_with_food(BoardTopology::Walled, Point2D::new(4, 3));
+
+        let (_, reward, done, info) = env.step(Direction::Right);
+        assert!(matches!(info.outcome, StepOutcome::Ate));
+        assert_eq!((reward, done, info.score, info.length), (1.0, false, 10, 2));
+
+        let mut env = env_with_food(BoardTopology::Walled, Point2D::new(1, 1));
+        assert_eq!(env.step(Direction::Right).1, -0.01);
+        assert_eq!(env.step(Direction::Right).1, -0.01);
+
+        let (_, reward, done, info) = env.step(Direction::Right);
+        assert!(matches!(info.outcome, StepOutcome::RoundOver(_)));
+        assert_eq!((reward, done, info.truncated), (-1.0, true, false));
+
+        let (_, reward, done, info) = env.step(Direction::Right);
+        assert!(matches!(info.outcome, StepOutcome::Idle));
+        assert_eq!((reward, done), (0.0, true));
+    }
+
+    #[test]
+    fn episodes_without_food_are_truncated() {
+        let mut env = env_with_food(BoardTopology::Wrap, Point2D::new(3, 0));
+        env.max_idle_steps = 3;
+
+        assert!(!env.step(Direction::Right).2);
+        assert!(!env.step(Direction::Right).2);
+
+        let (_, reward, done, info) = env.step(Direction::Right);
+        assert!(matches!(info.outcome, StepOutcome::Moved));
+        assert_eq!((reward, done, info.truncated), (-0.01, true, true));
+    }
+
+    #[test]
+    fn reset_with_a_seed_repeats_the_episode() {
+        let mut env = SnakeEnv::new(8, 8, BoardTopology::Wrap).unwrap();
+        let mut play = |seed: u64| {
+            let mut observations = vec![env.reset(seed)];
+            for action in ACTIONS.iter().cycle().take(30) {
+                observations.push(env.step(*action).0);
+            }
+            observations
+        };
+
+        assert_eq!(play(42), play(42));
+        assert_ne!(play(42), play(43));
+    }
+}
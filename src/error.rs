@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fmt;
+use crate::point_2d::Point2D;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnakeError {
+    /// Zero or negative grid dimensions.
+    InvalidDimensions { cols: i32, rows: i32 },
+    /// Valid dimensions, but too small to play on.
+    BoardTooSmall { cols: usize, rows: usize, min_cols: usize, min_rows: usize },
+    /// Fewer than one player, or more than the board has room for.
+    InvalidPlayerCount { players: usize, max: usize },
+    /// Position or region outside of a grid of `size`.
+    OutOfBounds { pos: Point2D<i32>, size: Point2D<i32> },
+    /// Malformed level file, `line` and `column` are 1-based.
+    LevelParse { line: usize, column: usize, message: String },
+    /// Malformed replay file, `line` is 1-based, 0 when the file ended early.
+    ReplayParse { line: usize, message: String },
+    /// Malformed controller script, `line` is 1-based.
+    ScriptParse { line: usize, message: String },
+    /// Save data that cannot be decoded into a game.
+    SaveFormat { message: String },
+    /// Save written by a schema version this build cannot migrate.
+    UnsupportedSaveVersion { version: u32, supported: u32 },
+    /// Board whose free cells cannot be visited in a single closed loop.
+    NoHamiltonianCycle { cols: usize, rows: usize },
+    /// Feature that only handles player one, asked for in a game of several players.
+    SinglePlayerOnly { feature: String, players: usize },
+}
+
+impl fmt::Display for SnakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnakeError::InvalidDimensions { cols, rows } => {
+                write!(f, "Invalid dimensions {}x{}", cols, rows)
+            },
+            SnakeError::BoardTooSmall { cols, rows, min_cols, min_rows } => {
+                write!(f, "Game field must be {}x{} cells at least. {}x{} entered.", min_cols, min_rows, cols, rows)
+            },
+            SnakeError::InvalidPlayerCount { players, max } => {
+                write!(f, "Invalid player count {}, this board takes 1 to {} players", players, max)
+            },
+            SnakeError::OutOfBounds { pos, size } => {
+                write!(f, "Position [{}, {}] is out of {}x{} grid", pos.x, pos.y, size.x, size.y)
+            },
+            SnakeError::LevelParse { line, column, message } => {
+                write!(f, "Level parse error at {}:{}: {}", line, column, message)
+            },
+            SnakeError::ReplayParse { line, message } => {
+                write!(f, "Replay parse error at line {}: {}", line, message)
+            },
+            SnakeError::ScriptParse { line, message } => {
+                write!(f, "Script parse error at line {}: {}", line, message)
+            },
+            SnakeError::SaveFormat { message } => {
+                write!(f, "Invalid save: {}", message)
+            },
+            SnakeError::UnsupportedSaveVersion { version, supported } => {
+                write!(f, "Unsupported save version {}, this build reads up to version {}", version, supported)
+            },
+            SnakeError::NoHamiltonianCycle { cols, rows } => {
+                write!(f, "No Hamiltonian cycle over the free cells of the {}x{} board", cols, rows)
+            },
+            SnakeError::SinglePlayerOnly { feature, players } => {
+                write!(f, "{} supports a single player only, {} players requested", feature, players)
+            },
+        }
+    }
+}
+
+impl Error for SnakeError {}
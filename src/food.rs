@@ -0,0 +1,189 @@
+use std::fmt::Debug;
+use rand::{Rng, RngCore};
+use crate::point_2d::Point2D;
+use serde::{Deserialize, Serialize};
+
+/// What a placement strategy gets to see when choosing where the next food goes.
+pub struct PlacementContext<'a> {
+    pub field_size: Point2D<usize>,
+    pub heads: &'a [Point2D<i32>],  // heads of the living snakes
+    pub free_cells: &'a [usize],    // field indices of empty cells
+}
+
+impl PlacementContext<'_> {
+    pub fn get_point_from_index(&self, index: usize) -> Point2D<i32> {
+        Point2D::new(
+            index as i32 % self.field_size.x as i32,
+            index as i32 / self.field_size.x as i32
+        )
+    }
+}
+
+/// Strategy choosing where food appears. `Send` so games can be stepped on worker threads.
+pub trait FoodPlacement: Debug + Send {
+    /// Returns the field index of the cell to put food on, `None` when nothing fits.
+    /// The returned index must be one of `context.free_cells`.
+    fn place(&mut self, context: &PlacementContext, rng: &mut dyn RngCore) -> Option<usize>;
+}
+
+/// Any empty cell with the same probability.
+#[derive(Debug, Default)]
+pub struct UniformRandom;
+
+impl FoodPlacement for UniformRandom {
+    fn place(&mut self, context: &PlacementContext, rng: &mut dyn RngCore) -> Option<usize> {
+        if context.free_cells.is_empty() {
+            return None;
+        }
+
+        Some(context.free_cells[rng.gen_range(0..context.free_cells.len())])
+    }
+}
+
+/// One of the empty cells with the largest manhattan distance from the nearest snake head.
+#[derive(Debug, Default)]
+pub struct FarFromHead;
+
+impl FoodPlacement for FarFromHead {
+    fn place(&mut self, context: &PlacementContext, rng: &mut dyn RngCore) -> Option<usize> {
+        let distance = |index: usize| {
+            let pos = context.get_point_from_index(index);
+            context.heads
+                .iter()
+                .map(|head| (pos.x - head.x).abs() + (pos.y - head.y).abs())
+                .min()
+                .unwrap_or(0)
+        };
+
+        let max_distance = context.free_cells.iter().map(|i| distance(*i)).max()?;
+        let farthest: Vec<usize> = context.free_cells
+            .iter()
+            .copied()
+            .filter(|i| distance(*i) == max_distance)
+            .collect();
+
+        Some(farthest[rng.gen_range(0..farthest.len())])
+    }
+}
+
+/// Cycles through a fixed list of positions, skipping the occupied ones.
+/// Meant for tests and scripted scenarios where food must show up at known places.
+#[derive(Debug)]
+pub struct FixedSequence {
+    positions: Vec<Point2D<i32>>,
+    next: usize,
+}
+
+impl FixedSequence {
+    pub fn new(positions: Vec<Point2D<i32>>) -> FixedSequence {
+        FixedSequence { positions, next: 0 }
+    }
+}
+
+impl FoodPlacement for FixedSequence {
+    fn place(&mut self, context: &PlacementContext, _rng: &mut dyn RngCore) -> Option<usize> {
+        for _ in 0..self.positions.len() {
+            let pos = self.positions[self.next];
+            self.next = (self.next + 1) % self.positions.len();
+
+            let free = context.free_cells
+                .iter()
+                .copied()
+                .find(|i| context.get_point_from_index(*i) == pos);
+
+            if free.is_some() {
+                return free;
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FoodSpawner {
+    #[serde(skip, default = "default_placement")]
+    pub placement: Box<dyn FoodPlacement>,  // not saved, a loaded game places food uniformly
+    pub max_food: usize,    // number of food items kept on the field at once
+}
+
+impl FoodSpawner {
+    pub fn new(placement: Box<dyn FoodPlacement>, max_food: usize) -> FoodSpawner {
+        FoodSpawner { placement, max_food }
+    }
+}
+
+fn default_placement() -> Box<dyn FoodPlacement> {
+    Box::new(UniformRandom)
+}
+
+impl Default for FoodSpawner {
+    fn default() -> Self {
+        FoodSpawner::new(default_placement(), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::GameRng;
+    use crate::{BoardTopology, CellType, Direction, Game, StepOutcome};
+
+    fn context(free_cells: &[usize]) -> PlacementContext<'_> {
+        PlacementContext { field_size: Point2D::new(4, 4), heads: &[], free_cells }
+    }
+
+    fn food(game: &Game) -> Vec<Point2D<i32>> {
+        game.field
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.cell_type == CellType::Food)
+            .map(|(index, _)| game.get_point_from_index(index))
+            .collect()
+    }
+
+    #[test]
+    fn uniform_random_picks_free_cells() {
+        let free_cells = [1, 6, 9, 14];
+        let mut rng = GameRng::new(3);
+
+        let picks: Vec<usize> = (0..50).filter_map(|_| UniformRandom.place(&context(&free_cells), &mut rng)).collect();
+        assert_eq!(picks.len(), 50);
+        assert!(free_cells.iter().all(|cell| picks.contains(cell)));
+        assert!(picks.iter().all(|pick| free_cells.contains(pick)));
+
+        assert_eq!(UniformRandom.place(&context(&[]), &mut rng), None);
+    }
+
+    #[test]
+    fn fixed_sequence_skips_occupied_cells_and_wraps_around() {
+        let mut placement = FixedSequence::new(vec![Point2D::new(1, 0), Point2D::new(2, 0), Point2D::new(3, 0)]);
+        let mut rng = GameRng::new(0);
+
+        assert_eq!(placement.place(&context(&[1, 2, 3]), &mut rng), Some(1));
+        assert_eq!(placement.place(&context(&[1, 3]), &mut rng), Some(3));
+        assert_eq!(placement.place(&context(&[1, 2, 3]), &mut rng), Some(1));
+        assert_eq!(placement.place(&context(&[5, 6]), &mut rng), None);
+    }
+
+    #[test]
+    fn spawner_keeps_max_food_on_the_field() {
+        let mut game = Game::new(8, 8, BoardTopology::Walled, 0).unwrap();
+        for cell in game.field.iter_mut().filter(|cell| cell.cell_type == CellType::Food) {
+            cell.cell_type = CellType::Empty;
+        }
+
+        // both right in front of the head at (4, 4)
+        let positions = vec![Point2D::new(5, 4), Point2D::new(6, 4), Point2D::new(1, 1)];
+        game.set_food_spawner(FoodSpawner::new(Box::new(FixedSequence::new(positions)), 2));
+        assert_eq!(food(&game), [Point2D::new(5, 4), Point2D::new(6, 4)]);
+
+        game.play(Direction::Right);
+        assert!(matches!(game.step(None), StepOutcome::Ate));
+        assert_eq!(food(&game), [Point2D::new(1, 1), Point2D::new(6, 4)]);
+
+        // the sequence starts over, but all of its cells are taken
+        assert!(matches!(game.step(None), StepOutcome::Ate));
+        assert_eq!(food(&game), [Point2D::new(1, 1)]);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::controller::Controller;
+use crate::error::SnakeError;
+use crate::{BoardTopology, CellType, Direction, Game, GameState};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+/// Player that never loses on the plain boards of `Game::new`.
+///
+/// The snake follows a closed loop through every free cell, so its body always lies on the
+/// loop between the tail and the head and the cells ahead of the head are free. Following
+/// the loop fills the whole field, one lap per meal at worst.
+///
+/// It steers player one and takes the field for its own, so games of several players are
+/// rejected.
+///
+/// With `shortcuts` on, the snake skips parts of the loop on its way to food as long as it
+/// lands ahead of its head and behind its tail, and only while it fills less than half of
+/// the loop. This keeps the guarantee and finishes the round much faster.
+#[derive(Debug, Clone)]
+pub struct HamiltonianSolver {
+    pub shortcuts: bool,
+    cycle: Vec<usize>,      // field indices in loop order
+    position: Vec<usize>,   // loop position of every field index, `usize::MAX` off the loop
+}
+
+impl HamiltonianSolver {
+    /// Builds the loop for the board of `game`.
+    /// The free area must be a rectangle (the field inside the border ring, or the whole
+    /// field when it wraps) with an even number of columns or rows.
+    pub fn new(game: &Game) -> Result<HamiltonianSolver, SnakeError> {
+        if game.snakes.len() > 1 {
+            return Err(SnakeError::SinglePlayerOnly { feature: "Hamiltonian solver".to_string(), players: game.snakes.len() });
+        }
+
+        let (cols, rows) = (game.field_size.x, game.field_size.y);
+        let no_cycle = SnakeError::NoHamiltonianCycle { cols, rows };
+
+        let (origin, width, height) = match game.topology {
+            BoardTopology::Walled => (1, cols.saturating_sub(2), rows.saturating_sub(2)),
+            BoardTopology::Wrap => (0, cols, rows),
+        };
+
+        if width < 2 || height < 2 || (width % 2 == 1 && height % 2 == 1) {
+            return Err(no_cycle);
+        }
+
+        // along the rows, back up the first column; transposed when the row count is odd
+        let to_index = |a: usize, b: usize| match height % 2 {
+            0 => (origin + b) * cols + origin + a,
+            _ => (origin + a) * cols + origin + b,
+        };
+        let (lanes, lane_length) = if height % 2 == 0 { (height, width) } else { (width, height) };
+
+        let mut cycle = Vec::with_capacity(width * height);
+        for lane in 0..lanes {
+            if lane % 2 == 0 {
+                cycle.extend((1..lane_length).map(|a| to_index(a, lane)));
+            } else {
+                cycle.extend((1..lane_length).rev().map(|a| to_index(a, lane)));
+            }
+        }
+        cycle.extend((0..lanes).rev().map(|lane| to_index(0, lane)));
+
+        let mut position = vec![usize::MAX; game.field.len()];
+        for (i, index) in cycle.iter().enumerate() {
+            position[*index] = i;
+        }
+
+        // every cell but the border has to be on the loop, so levels with inner walls do not fit
+        let fits = game.field
+            .iter()
+            .enumerate()
+            .all(|(index, cell)| (cell.cell_type == CellType::Border) == (position[index] == usize::MAX));
+
+        if !fits {
+            return Err(no_cycle);
+        }
+
+        Ok(HamiltonianSolver { shortcuts: true, cycle, position })
+    }
+
+    /// Field indices of the loop, in the order the snake follows it.
+    pub fn cycle(&self) -> &[usize] {
+        &self.cycle
+    }
+
+    /// Direction to take in the next step, `None` when the round is over.
+    pub fn next_direction(&self, game: &Game) -> Option<Direction> {
+        if let GameState::GameOver(_) = game.state {
+            return None;
+        }
+
+        let snake = game.snake();
+        let head = snake.head_index;
+        let head_position = *self.position.get(head).filter(|position| **position != usize::MAX)?;
+        let n = self.cycle.len();
+        let distance = |index: usize| (self.position[index] + n - head_position) % n;
+
+        let mut target = self.cycle[(head_position + 1) % n];
+
+        if self.shortcuts && snake.len() < n / 2 {
+            let tail_distance = match snake.len() {
+                1 => n,
+                _ => distance(*snake.body.back()?),
+            };
+            let food_distance = game.field
+                .iter()
+                .enumerate()
+                .filter(|(index, cell)| cell.cell_type == CellType::Food && self.position[*index] != usize::MAX)
+                .map(|(index, _)| distance(index))
+                .min()
+                .unwrap_or(n);
+
+            // the farthest free neighbor that does not skip the food or overtake the tail
+            let shortcut = DIRECTIONS
+                .iter()
+                .filter(|direction| snake.is_turn_allowed(snake.direction, **direction))
+                .filter_map(|direction| game.neighbor_index(head, *direction))
+                .filter(|index| matches!(game.field[*index].cell_type, CellType::Empty | CellType::Food))
+                .filter(|index| self.position[*index] != usize::MAX)
+                .filter(|index| distance(*index) < tail_distance && distance(*index) <= food_distance)
+                .max_by_key(|index| distance(*index));
+
+            if let Some(index) = shortcut {
+                target = index;
+            }
+        }
+
+        DIRECTIONS
+            .iter()
+            .copied()
+            .find(|direction| game.neighbor_index(head, *direction) == Some(target))
+    }
+}
+
+impl Controller for HamiltonianSolver {
+    fn next_direction(&mut self, game: &Game) -> Option<Direction> {
+        HamiltonianSolver::next_direction(self, game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::play_round;
+    use crate::level::Level;
+    use crate::GameOverType;
+
+    fn assert_fills_field(cols: usize, rows: usize, topology: BoardTopology) {
+        for shortcuts in [false, true] {
+            for seed in 0..3 {
+                let mut game = Game::new(cols, rows, topology, seed).unwrap();
+                let mut solver = HamiltonianSolver::new(&game).unwrap();
+                solver.shortcuts = shortcuts;
+
+                // one lap of the cycle per meal at most
+                let max_steps = game.field.len() * game.field.len();
+                let stats = play_round(&mut game, &mut solver, max_steps);
+
+                assert_eq!(
+                    stats.and_then(|stats| stats.game_over),
+                    Some(GameOverType::PlaygroundFilled),
+                    "{}x{} {:?}, shortcuts {}, seed {}",
+                    cols, rows, topology, shortcuts, seed,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fills_walled_boards() {
+        assert_fills_field(6, 6, BoardTopology::Walled);
+        assert_fills_field(12, 12, BoardTopology::Walled);
+        assert_fills_field(10, 7, BoardTopology::Walled);
+        assert_fills_field(7, 10, BoardTopology::Walled);
+    }
+
+    #[test]
+    fn fills_wrapping_boards() {
+        assert_fills_field(8, 8, BoardTopology::Wrap);
+        assert_fills_field(8, 5, BoardTopology::Wrap);
+        assert_fills_field(5, 8, BoardTopology::Wrap);
+    }
+
+    #[test]
+    fn cycle_visits_every_free_cell_once() {
+        let game = Game::new(10, 7, BoardTopology::Walled, 0).unwrap();
+        let solver = HamiltonianSolver::new(&game).unwrap();
+
+        let mut cycle = solver.cycle().to_vec();
+        cycle.sort();
+        cycle.dedup();
+        assert_eq!(cycle.len(), 8 * 5);
+        assert!(cycle.iter().all(|index| game.field[*index].cell_type != CellType::Border));
+    }
+
+    #[test]
+    fn rejects_odd_boards() {
+        for (cols, rows, topology) in [(9, 9, BoardTopology::Walled), (7, 5, BoardTopology::Wrap)] {
+            let game = Game::new(cols, rows, topology, 0).unwrap();
+
+            assert!(matches!(
+                HamiltonianSolver::new(&game),
+                Err(SnakeError::NoHamiltonianCycle { cols: c, rows: r }) if (c, r) == (cols, rows)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_levels_with_inner_walls() {
+        for name in ["cross", "rooms"] {
+            let game = Game::from_level(Level::bundled(name).unwrap(), 0).unwrap();
+
+            assert!(matches!(HamiltonianSolver::new(&game), Err(SnakeError::NoHamiltonianCycle { .. })));
+        }
+    }
+
+    #[test]
+    fn rejects_games_of_several_players() {
+        let game = Game::with_players(12, 12, BoardTopology::Walled, 2, 0).unwrap();
+
+        assert!(matches!(HamiltonianSolver::new(&game), Err(SnakeError::SinglePlayerOnly { players: 2, .. })));
+    }
+}
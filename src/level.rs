@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::SnakeError;
+use crate::matrix_2d::Matrix2D;
+use crate::point_2d::Point2D;
+use crate::{BoardTopology, Direction, MIN_FIELD_SIZE};
+
+/// Levels shipped with the game, as `(name, level file)`.
+pub const BUNDLED_LEVELS: [(&str, &str); 3] = [
+    ("box", include_str!("../levels/box.txt")),
+    ("cross", include_str!("../levels/cross.txt")),
+    ("rooms", include_str!("../levels/rooms.txt")),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LevelCell {
+    #[default]
+    Empty,
+    Wall,
+    Food,
+}
+
+/// Playing field layout loaded from a plain-text level file.
+///
+/// Every row of the map is one line of the file:
+/// - `#` wall, `.` empty cell, `*` food,
+/// - `S` spawn facing right, or `>` `<` `^` `v` spawn facing that way (exactly one per level).
+///
+/// Lines starting with `;` are comments. Lines starting with `@` set options:
+/// `@name <name>` and `@topology walled|wrap` (walled by default).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub topology: BoardTopology,
+    pub cells: Matrix2D<LevelCell>,
+    pub spawn: Point2D<i32>,
+    pub direction: Direction,
+}
+
+fn parse_error(line: usize, column: usize, message: impl Into<String>) -> SnakeError {
+    SnakeError::LevelParse { line, column, message: message.into() }
+}
+
+impl Level {
+    /// The layout of `Game::new`: an empty field with the snake in the middle facing right,
+    /// surrounded by walls unless the topology wraps.
+    pub fn open(cols: usize, rows: usize, topology: BoardTopology) -> Result<Level, SnakeError> {
+        if cols < MIN_FIELD_SIZE || rows < MIN_FIELD_SIZE {
+            return Err(SnakeError::BoardTooSmall { cols, rows, min_cols: MIN_FIELD_SIZE, min_rows: MIN_FIELD_SIZE });
+        }
+
+        let size = Point2D::new(cols as i32, rows as i32);
+        let walled = topology == BoardTopology::Walled;
+        let cells = Matrix2D::from_fn(&size, |pos| {
+            let on_edge = pos.x == 0 || pos.y == 0 || pos.x == size.x - 1 || pos.y == size.y - 1;
+            if walled && on_edge { LevelCell::Wall } else { LevelCell::Empty }
+        })?;
+
+        Ok(Level {
+            name: String::new(),
+            topology,
+            cells,
+            spawn: Point2D::new(size.x / 2, size.y / 2),
+            direction: Direction::Right,
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Level, SnakeError> {
+        let mut name = String::new();
+        let mut topology = BoardTopology::Walled;
+        let mut rows: Vec<Vec<LevelCell>> = Vec::new();
+        let mut spawn: Option<(Point2D<i32>, Direction)> = None;
+        let mut last_line = 0;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_no = line_index + 1;
+            let line = line.trim_end();
+            last_line = line_no;
+
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(option) = line.strip_prefix('@') {
+                match option.split_once(' ') {
+                    Some(("name", value)) => name = value.trim().to_string(),
+                    Some(("topology", "walled")) => topology = BoardTopology::Walled,
+                    Some(("topology", "wrap")) => topology = BoardTopology::Wrap,
+                    Some(("topology", _)) => return Err(parse_error(line_no, 11, "topology must be `walled` or `wrap`")),
+                    _ => return Err(parse_error(line_no, 2, format!("unknown option `{}`", option))),
+                }
+                continue;
+            }
+
+            let y = rows.len() as i32;
+            let mut row = Vec::with_capacity(line.len());
+
+            for (column_index, c) in line.chars().enumerate() {
+                let column_no = column_index + 1;
+                let spawn_direction = match c {
+                    'S' | '>' => Some(Direction::Right),
+                    '<' => Some(Direction::Left),
+                    '^' => Some(Direction::Up),
+                    'v' => Some(Direction::Down),
+                    _ => None,
+                };
+
+                let cell = match (c, spawn_direction) {
+                    (_, Some(direction)) => {
+                        if spawn.is_some() {
+                            return Err(parse_error(line_no, column_no, "second spawn point"));
+                        }
+                        spawn = Some((Point2D::new(column_index as i32, y), direction));
+                        LevelCell::Empty
+                    },
+                    ('#', _) => LevelCell::Wall,
+                    ('.', _) => LevelCell::Empty,
+                    ('*', _) => LevelCell::Food,
+                    _ => return Err(parse_error(line_no, column_no, format!("unexpected character `{}`", c))),
+                };
+                row.push(cell);
+            }
+
+            if let Some(first_row) = rows.first() {
+                if row.len() != first_row.len() {
+                    let column = row.len().min(first_row.len()) + 1;
+                    return Err(parse_error(
+                        line_no,
+                        column,
+                        format!("row has {} cells, the first row has {}", row.len(), first_row.len()),
+                    ));
+                }
+            }
+
+            rows.push(row);
+        }
+
+        let (cols, row_count) = (rows.first().map_or(0, |row| row.len()), rows.len());
+        if cols < MIN_FIELD_SIZE || row_count < MIN_FIELD_SIZE {
+            return Err(parse_error(
+                last_line,
+                1,
+                format!("level must be {}x{} cells at least, found {}x{}", MIN_FIELD_SIZE, MIN_FIELD_SIZE, cols, row_count),
+            ));
+        }
+
+        let (spawn, direction) = spawn.ok_or_else(|| parse_error(last_line, 1, "missing spawn point"))?;
+        let cells = Matrix2D::from_fn(
+            &Point2D::new(cols as i32, row_count as i32),
+            |pos| rows[pos.y as usize][pos.x as usize],
+        )?;
+
+        Ok(Level { name, topology, cells, spawn, direction })
+    }
+
+    /// Bundled level by name.
+    pub fn bundled(name: &str) -> Option<Level> {
+        BUNDLED_LEVELS
+            .iter()
+            .find(|(level_name, _)| *level_name == name)
+            .map(|(_, text)| Level::parse(text).expect("bundled levels are valid"))
+    }
+
+    pub fn get_size(&self) -> Point2D<i32> {
+        self.cells.get_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error_at(text: &str) -> (usize, usize) {
+        match Level::parse(text) {
+            Err(SnakeError::LevelParse { line, column, .. }) => (line, column),
+            other => panic!("{:?} parsed as {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn parses_cells_spawn_and_options() {
+        let level = Level::parse("; comment\n@name Corner\n@topology wrap\n.....\n.#*..\n..v..\n.....\n.....\n").unwrap();
+
+        assert_eq!(level.name, "Corner");
+        assert_eq!(level.topology, BoardTopology::Wrap);
+        assert_eq!(level.get_size(), Point2D::new(5, 5));
+        assert_eq!(level.spawn, Point2D::new(2, 2));
+        assert_eq!(level.direction, Direction::Down);
+        assert_eq!(level.cells[Point2D::new(1, 1)], LevelCell::Wall);
+        assert_eq!(level.cells[Point2D::new(2, 1)], LevelCell::Food);
+        assert_eq!(level.cells[level.spawn], LevelCell::Empty);
+    }
+
+    #[test]
+    fn reports_line_and_column_of_errors() {
+        let rows = ".....\n.....\n.....\n.....\n";
+
+        // ragged row, at the first cell past the shorter row
+        assert_eq!(parse_error_at(&format!("S....\n....\n{}", rows)), (2, 5));
+        assert_eq!(parse_error_at(&format!("S....\n......\n{}", rows)), (2, 6));
+        // unknown character
+        assert_eq!(parse_error_at(&format!("S..x.\n{}", rows)), (1, 4));
+        // second spawn
+        assert_eq!(parse_error_at(&format!("S....\n...<.\n{}", rows)), (2, 4));
+        // missing spawn, reported at the last line
+        assert_eq!(parse_error_at(&format!(".....\n{}", rows)), (5, 1));
+        // bad options
+        assert_eq!(parse_error_at(&format!("@topology round\nS....\n{}", rows)), (1, 11));
+        assert_eq!(parse_error_at(&format!("@size 5\nS....\n{}", rows)), (1, 2));
+        // too small
+        assert_eq!(parse_error_at("S....\n.....\n"), (2, 1));
+    }
+
+    #[test]
+    fn bundled_levels_parse() {
+        for (name, text) in BUNDLED_LEVELS {
+            let level = Level::parse(text).unwrap_or_else(|e| panic!("{}: {}", name, e));
+
+            assert_eq!(level.cells[level.spawn], LevelCell::Empty, "{}", name);
+            assert!(Level::bundled(name).is_some());
+        }
+        assert!(Level::bundled("missing").is_none());
+    }
+
+    #[test]
+    fn open_level_matches_a_new_game() {
+        let level = Level::open(8, 6, BoardTopology::Walled).unwrap();
+
+        assert_eq!(level.spawn, Point2D::new(4, 3));
+        assert_eq!(level.cells.iter().filter(|cell| **cell == LevelCell::Wall).count(), 2 * 8 + 2 * 4);
+        assert!(matches!(Level::open(4, 6, BoardTopology::Wrap), Err(SnakeError::BoardTooSmall { .. })));
+    }
+}
@@ -1,25 +1,47 @@
 pub mod point_2d;
-pub mod game_state;
-mod matrix_2d;
+pub mod matrix_2d;
+pub mod error;
+pub mod food;
+pub mod rng;
+pub mod score;
+pub mod speed;
+pub mod ticker;
+pub mod replay;
+pub mod save;
+pub mod level;
+pub mod obstacles;
+pub mod autopilot;
+pub mod hamiltonian;
+pub mod controller;
+pub mod env;
+pub mod batch;
+#[cfg(feature = "render")]
+pub mod render;
+
+#[cfg(feature = "render")]
+pub use crate::render::RenderSettings;
 
-use std::cell::RefCell;
 use std::cmp::PartialEq;
+use std::collections::VecDeque;
 use crate::point_2d::Point2D;
+use crate::matrix_2d::Matrix2D;
+use crate::error::SnakeError;
+use crate::level::{Level, LevelCell};
+use crate::food::{FoodSpawner, PlacementContext};
+use crate::rng::GameRng;
+use crate::score::{RoundStats, Score, ScoreRules};
+use crate::speed::SpeedCurve;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 
-use graphics::math::Scalar;
-use graphics::rectangle;
-use graphics::types::Rectangle;
-use piston::Size;
-use std::rc::Rc;
-use opengl_graphics::{Texture, TextureSettings};
 
-static CELL_CODE_INIT_HEAD: i32 = 1;
-static CELL_CODE_EMPTY: i32 = 0;
-static CELL_CODE_BORDER: i32 = -1;
-static CELL_CODE_FOOD: i32 = -2;
+/// Smallest field size accepted by `Game::new`, in both dimensions.
+pub const MIN_FIELD_SIZE: usize = 5;
 
+/// How many direction changes can wait for the next game steps.
+pub const INPUT_QUEUE_CAPACITY: usize = 3;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Left,
     Right,
@@ -27,7 +49,44 @@ pub enum Direction {
     Down,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl Direction {
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+
+    pub fn offset(self) -> Point2D<i32> {
+        match self {
+            Direction::Right => Point2D::new(1i32, 0i32),
+            Direction::Left => Point2D::new(-1i32, 0i32),
+            Direction::Up => Point2D::new(0i32, -1i32),
+            Direction::Down => Point2D::new(0i32, 1i32),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoardTopology {
+    Walled,     // surrounded by a ring of border cells
+    Wrap,       // leaving one edge re-enters from the opposite one, no border
+}
+
+impl BoardTopology {
+    /// Maps `pos` onto a field of `size` cells. Returns `None` for positions off a walled field.
+    pub fn wrap(self, pos: Point2D<i32>, size: Point2D<i32>) -> Option<Point2D<i32>> {
+        match self {
+            BoardTopology::Walled if pos.x >= 0 && pos.y >= 0 && pos.x < size.x && pos.y < size.y => Some(pos),
+            BoardTopology::Walled => None,
+            BoardTopology::Wrap => Some(Point2D::new(pos.x.rem_euclid(size.x), pos.y.rem_euclid(size.y))),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum SnakeBodyPart {
     Head(usize),
     Body(usize),
@@ -36,21 +95,21 @@ pub enum SnakeBodyPart {
 
 impl PartialEq for SnakeBodyPart {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (SnakeBodyPart::Head(_), SnakeBodyPart::Head(_)) => true,
-            (SnakeBodyPart::Body(_), SnakeBodyPart::Body(_)) => true,
-            (SnakeBodyPart::Tail(_), SnakeBodyPart::Tail(_)) => true,
-            _ => false,
-        }
+        matches!(
+            (self, other),
+            (SnakeBodyPart::Head(_), SnakeBodyPart::Head(_))
+                | (SnakeBodyPart::Body(_), SnakeBodyPart::Body(_))
+                | (SnakeBodyPart::Tail(_), SnakeBodyPart::Tail(_))
+        )
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum CellType {
     Uninitialized,  // for render optimization
     Empty,
     Border,
-    Snake(SnakeBodyPart),
+    Snake(usize, SnakeBodyPart),    // owner id, index into `Game::snakes`
     Food,
 }
 
@@ -60,121 +119,299 @@ impl PartialEq for CellType {
             (CellType::Empty, CellType::Empty) => true,
             (CellType::Border, CellType::Border) => true,
             (CellType::Food, CellType::Food) => true,
-            (CellType::Snake(part1), CellType::Snake(part2)) => part1 == part2,
+            (CellType::Snake(owner1, part1), CellType::Snake(owner2, part2)) => owner1 == owner2 && part1 == part2,
             _ => false,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameOverType {
     PlaygroundFilled,
     BorderHit,
-    SelfBite
+    SelfBite,
+    SnakeHit,   // ran into the body of another snake
+    HeadOn,     // two heads met in one cell or passed through each other
 }
 
+/// What a single call to `Game::step` did.
 #[derive(Debug, Copy, Clone)]
+pub enum StepOutcome {
+    Idle,       // the game is not being played, nothing changed
+    Moved,
+    Ate,        // at least one snake ate
+    RoundOver(RoundStats),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameState {
     Paused,
     Playing,
     GameOver(GameOverType),
 }
 
-pub struct RenderSettings {
-    pub viewport_size: Point2D<usize>,
-    pub grid_size: Point2D<usize>,
-    pub square_size: Point2D<Scalar>,
-    pub square: Rectangle,
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Cell {
+    pub pos: Point2D<i32>,
+    pub cell_type: CellType,
+    pub rendered_cell_type: CellType,   // for render optimization
 }
 
-impl RenderSettings {
-    pub fn new(viewport_size: [usize; 2], grid_size: [usize; 2]) -> RenderSettings {
-        let viewport_size = Point2D::new_from_array(viewport_size);
-        let grid_size = Point2D::new_from_array(grid_size);
-        let square_size = Point2D {
-            x: viewport_size.x as Scalar / grid_size.x as Scalar,
-            y: viewport_size.y as Scalar / grid_size.y as Scalar,
-        };
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            pos: Point2D::new(-1, -1),
+            cell_type: CellType::Uninitialized,
+            rendered_cell_type: CellType::Uninitialized,
+        }
+    }
+}
 
-        RenderSettings {
-            viewport_size,
-            grid_size,
-            square_size,
-            square: rectangle::rectangle_by_corners(
-                0.0,
-                0.0,
-                square_size.x - 1.,
-                square_size.y - 1.,
-            )
+/// One player's snake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snake {
+    pub head_index: usize,
+    pub body: VecDeque<usize>,  // field indices of all segments, head first
+    pub direction: Direction,
+    pub input_queue: VecDeque<Direction>,   // one direction change is consumed per step
+    pub score: Score,
+    pub death: Option<GameOverType>,    // how the snake died, `None` while it is alive
+}
+
+impl Snake {
+    fn new(head_index: usize, direction: Direction) -> Snake {
+        Snake {
+            head_index,
+            body: VecDeque::from([head_index]),
+            direction,
+            input_queue: VecDeque::with_capacity(INPUT_QUEUE_CAPACITY),
+            score: Score::default(),
+            death: None,
         }
     }
 
-    pub fn get_viewport_size(&self) -> Size {
-        Size { width: self.viewport_size.x as f64, height: self.viewport_size.y as f64 }
+    pub fn len(&self) -> usize {
+        self.body.len()
     }
-}
 
-#[derive(Debug)]
-pub struct Cell {
-    pub pos: Point2D<i32>,
-    pub cell_type: CellType,
-    pub rendered_cell_type: CellType,   // for render optimization
-    link_to_tail: Option<Rc<Cell>>,
+    /// True for a snake that died while other snakes played on, its body was removed from the field.
+    /// The snakes of a round that is over keep their bodies.
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.death.is_none()
+    }
+
+    /// Queues a direction change for one of the following steps.
+    /// The turn is checked against the direction that will be in effect when it gets consumed,
+    /// so two quick turns within one step are both kept and cannot reverse the snake.
+    pub fn set_movement_direction(&mut self, direction: Direction) {
+        let effective_direction = *self.input_queue.back().unwrap_or(&self.direction);
+
+        if direction == effective_direction
+            || !self.is_turn_allowed(effective_direction, direction)
+            || self.input_queue.len() >= INPUT_QUEUE_CAPACITY {
+            return;
+        }
+
+        self.input_queue.push_back(direction);
+    }
+
+    /// Direction rules based on the snake length:
+    /// - length 1: any direction, a lone head has no body to reverse into,
+    /// - length 2: no reversal, the head would swap places with the tail,
+    /// - longer: no reversal, the head would bite the body right behind it.
+    pub fn is_turn_allowed(&self, from: Direction, to: Direction) -> bool {
+        match self.len() {
+            0 | 1 => true,
+            _ => to != from.opposite(),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Game {
     pub state: GameState,
     pub field_size: Point2D<usize>,
-    pub field: Vec<Rc<RefCell<Cell>>>,  // 2d array in single 1d container
-    pub snake_head_index: usize,
-    pub direction: Direction,
-    pub snake_length: usize,
+    pub topology: BoardTopology,
+    pub level: Option<Level>,   // layout of every round, a plain field when None
+    pub field: Matrix2D<Cell>,
+    pub snakes: Vec<Snake>,     // player one first, there is always at least one
     pub step_count: usize,
+    pub food_spawner: FoodSpawner,
+    pub score_rules: ScoreRules,
+    pub stats: RoundStats,  // statistics of the current round, summed over all snakes
+    pub speed_curve: SpeedCurve,
+    pub round: usize,
+    pub seed: u64,      // seed of the current round
+    pub rng: GameRng,   // the only source of randomness, same seed + same inputs = same game
 }
 
 impl Game {
-    pub fn new(cols: usize, rows: usize) -> Result<Game, String> {
-
-        if cols < 5 || rows < 5 {
-            return Err(format!("Game field must be 5x5 cells at least. {}x{} entered.", cols, rows));
-        }
-
-        let mut field: Vec<Rc<RefCell<Cell>>> = Vec::with_capacity(cols * rows);
-        
-        // put snake head in the middle
-        let snake_head_index = (cols * rows) / 2 + rows / 2;
-
-        for index in 0..(cols * rows) {
-            let pos = Point2D::new(index / cols, index % cols);
-            let cell_type = match (pos.x, pos.y) {
-                (0, _) => CellType::Border,
-                (_, y) if y == cols-1 => CellType::Border,
-                (_, 0) => CellType::Border,
-                (x, _) if x == rows-1 => CellType::Border,
-                (_, _) if index == snake_head_index => CellType::Snake(SnakeBodyPart::Head(1)),
-                (_, _) => CellType::Empty,
-            };
+    pub fn new(cols: usize, rows: usize, topology: BoardTopology, seed: u64) -> Result<Game, SnakeError> {
+        Game::create(cols, rows, topology, None, 1, seed)
+    }
 
-            let pos_i32: Point2D<i32> = Point2D::new(pos.x as i32, pos.y as i32);
+    /// Game for `players` snakes on one field, spread over the middle column.
+    /// The round ends when at most one snake is left alive.
+    pub fn with_players(
+        cols: usize,
+        rows: usize,
+        topology: BoardTopology,
+        players: usize,
+        seed: u64,
+    ) -> Result<Game, SnakeError> {
+        Game::create(cols, rows, topology, None, players, seed)
+    }
 
-            field.push(Rc::new(RefCell::new(Cell {
-                pos: pos_i32,
-                cell_type,
-                rendered_cell_type: CellType::Uninitialized,
-                link_to_tail: None,
-            })));
+    /// Game whose rounds start with the walls, food and spawn point of `level`.
+    pub fn from_level(level: Level, seed: u64) -> Result<Game, SnakeError> {
+        let size = level.get_size();
+        Game::create(size.x as usize, size.y as usize, level.topology, Some(level), 1, seed)
+    }
+
+    fn create(
+        cols: usize,
+        rows: usize,
+        topology: BoardTopology,
+        level: Option<Level>,
+        players: usize,
+        seed: u64,
+    ) -> Result<Game, SnakeError> {
+
+        if cols < MIN_FIELD_SIZE || rows < MIN_FIELD_SIZE {
+            return Err(SnakeError::BoardTooSmall {
+                cols,
+                rows,
+                min_cols: MIN_FIELD_SIZE,
+                min_rows: MIN_FIELD_SIZE,
+            });
         }
 
-        Ok(Game {
+        // every snake gets a row of its own, off the border
+        let max_players = if level.is_some() { 1 } else { rows - 2 };
+        if players == 0 || players > max_players {
+            return Err(SnakeError::InvalidPlayerCount { players, max: max_players });
+        }
+
+        let mut game = Game {
             state: GameState::Paused,
             field_size: Point2D::new(cols, rows),
-            field,
-            snake_head_index,
-            direction: Direction::Right,
-            snake_length: 1usize,
+            topology,
+            level,
+            field: Matrix2D::new(&Point2D::new(cols as i32, rows as i32))?,
+            snakes: vec![Snake::new(0, Direction::Right); players],
             step_count: 0usize,
-        })
+            food_spawner: FoodSpawner::default(),
+            score_rules: ScoreRules::default(),
+            stats: RoundStats::default(),
+            speed_curve: SpeedCurve::default(),
+            round: 0usize,
+            seed,
+            rng: GameRng::new(seed),
+        };
+
+        game.reset_round();
+
+        Ok(game)
+    }
+
+    /// Starts the current round over, with the same seed and thus the same food placement.
+    pub fn restart(&mut self) {
+        self.rng = GameRng::new(self.seed);
+        self.reset_round();
+    }
+
+    /// Starts a fresh round seeded from the generator of the previous one.
+    pub fn new_round(&mut self) {
+        self.seed = self.rng.next_u64();
+        self.rng = GameRng::new(self.seed);
+        self.round += 1;
+        self.reset_round();
+    }
+
+    pub fn pause(&mut self) {
+        if let GameState::Playing = self.state {
+            self.state = GameState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let GameState::Paused = self.state {
+            self.state = GameState::Playing;
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.state {
+            GameState::Playing => self.pause(),
+            GameState::Paused => self.resume(),
+            GameState::GameOver(_) => {},
+        }
+    }
+
+    /// Field index and direction of every snake at the start of a round.
+    fn spawn_points(&self) -> Vec<(usize, Direction)> {
+        let (cols, rows) = (self.field_size.x, self.field_size.y);
+        let players = self.snakes.len();
+
+        match &self.level {
+            Some(level) => vec![(self.get_index_from_point(level.spawn), level.direction)],
+            // in the middle column, evenly spaced rows, every other snake facing left
+            None => (0..players)
+                .map(|player| {
+                    let row = (player + 1) * rows / (players + 1);
+                    let direction = if player % 2 == 0 { Direction::Right } else { Direction::Left };
+                    (row * cols + cols / 2, direction)
+                })
+                .collect(),
+        }
+    }
+
+    fn reset_round(&mut self) {
+        let cols = self.field_size.x;
+        let rows = self.field_size.y;
+        let walled = self.topology == BoardTopology::Walled;
+
+        for (index, cell) in self.field.iter_mut().enumerate() {
+            let pos = Point2D::new(index % cols, index / cols);
+            let cell_type = match (pos.x, pos.y, &self.level) {
+                (_, _, Some(level)) => match level.cells[index] {
+                    LevelCell::Empty => CellType::Empty,
+                    LevelCell::Wall => CellType::Border,
+                    LevelCell::Food => CellType::Food,
+                },
+                (_, _, None) if !walled => CellType::Empty,
+                (0, _, None) => CellType::Border,
+                (x, _, None) if x == cols-1 => CellType::Border,
+                (_, 0, None) => CellType::Border,
+                (_, y, None) if y == rows-1 => CellType::Border,
+                (_, _, None) => CellType::Empty,
+            };
+
+            *cell = Cell {
+                pos: Point2D::new(pos.x as i32, pos.y as i32),
+                cell_type,
+                rendered_cell_type: CellType::Uninitialized,
+            };
+        }
+
+        self.snakes = self.spawn_points()
+            .into_iter()
+            .map(|(head_index, direction)| Snake::new(head_index, direction))
+            .collect();
+        self.update_snake_cells();
+
+        self.state = GameState::Paused;
+        self.step_count = 0;
+        self.stats = RoundStats {
+            max_length: 1,
+            ..RoundStats::default()
+        };
+
+        self.spawn_food();
     }
 
     pub fn get_field_index(&self, x: i32, y: i32) -> usize {
@@ -192,75 +429,305 @@ impl Game {
         )
     }
 
-    pub fn cell_iter_mut(&mut self) -> CellIterator {
+    /// Player one's snake.
+    pub fn snake(&self) -> &Snake {
+        &self.snakes[0]
+    }
+
+    /// The last snake alive when a round of several players is over.
+    pub fn winner(&self) -> Option<usize> {
+        let mut alive = self.snakes.iter().enumerate().filter(|(_, snake)| snake.is_alive());
+
+        match (self.state, self.snakes.len(), alive.next(), alive.next()) {
+            (GameState::GameOver(_), 2.., Some((player, _)), None) => Some(player),
+            _ => None,
+        }
+    }
+
+    pub fn cell_iter(&self) -> CellIterator<'_> {
         CellIterator {
             game: self,
             iter_index: 0,
         }
     }
 
+    pub fn set_food_spawner(&mut self, food_spawner: FoodSpawner) {
+        self.food_spawner = food_spawner;
+        self.spawn_food();
+    }
+
+    /// Tops the field up to `food_spawner.max_food` food items.
+    pub fn spawn_food(&mut self) {
+        let mut food_count = 0usize;
+        let mut free_cells = Vec::new();
+        let heads: Vec<Point2D<i32>> = self.snakes
+            .iter()
+            .filter(|snake| snake.is_alive())
+            .map(|snake| self.get_point_from_index(snake.head_index))
+            .collect();
+
+        for (index, cell) in self.field.iter().enumerate() {
+            match cell.cell_type {
+                CellType::Food => food_count += 1,
+                CellType::Empty => free_cells.push(index),
+                _ => {},
+            }
+        }
+
+        while food_count < self.food_spawner.max_food {
+            let context = PlacementContext {
+                field_size: self.field_size,
+                heads: &heads,
+                free_cells: &free_cells,
+            };
+
+            let index = match self.food_spawner.placement.place(&context, &mut self.rng) {
+                Some(index) => index,
+                None => break,
+            };
+
+            self.field[index].cell_type = CellType::Food;
+            free_cells.retain(|i| *i != index);
+            food_count += 1;
+        }
+    }
+
+    /// Starts or resumes the round with player one heading to `direction`.
     pub fn play(&mut self, direction: Direction) {
+        self.play_player(0, direction);
+    }
+
+    /// Starts or resumes the round with the snake of `player` heading to `direction`.
+    pub fn play_player(&mut self, player: usize, direction: Direction) {
         self.state = GameState::Playing;
-        self.direction = direction;
+
+        if let Some(snake) = self.snakes.get_mut(player) {
+            snake.input_queue.clear();
+
+            if snake.is_turn_allowed(snake.direction, direction) {
+                snake.direction = direction;
+            }
+        }
     }
 
+    /// Queues a direction change of player one, see `Snake::set_movement_direction`.
     pub fn set_movement_direction(&mut self, direction: Direction) {
-        // we cannot move in opposite direction when length > 1
-        if self.field.get(self.snake_head_index).unwrap().borrow().cell_type.eq(&CellType::Snake(SnakeBodyPart::Head(1))) {
-            self.direction = direction;
-        }
-        else {
-            self.direction = match (self.direction, direction) {
-                (Direction::Right, Direction::Left) => Direction::Right,
-                (Direction::Left, Direction::Right) => Direction::Left,
-                (Direction::Up, Direction::Down) => Direction::Up,
-                (Direction::Down, Direction::Up) => Direction::Down,
-                (_, _) => direction,
-            };
+        self.set_player_direction(0, direction);
+    }
+
+    pub fn set_player_direction(&mut self, player: usize, direction: Direction) {
+        if let Some(snake) = self.snakes.get_mut(player) {
+            snake.set_movement_direction(direction);
         }
     }
 
-    pub fn update_game_state(&mut self) {
-        let head_pos_2d = self.get_point_from_index(self.snake_head_index);
+    pub fn is_on_field(&self, pos: Point2D<i32>) -> bool {
+        pos.x >= 0 && pos.y >= 0
+            && (pos.x as usize) < self.field_size.x
+            && (pos.y as usize) < self.field_size.y
+    }
 
-        let mut new_head_pos_2d = head_pos_2d + match self.direction {
-            Direction::Right => Point2D::new(1i32, 0i32),
-            Direction::Left => Point2D::new(-1i32, 0i32),
-            Direction::Up => Point2D::new(0i32, -1i32),
-            Direction::Down => Point2D::new(0i32, 1i32),
+    /// Maps `pos` onto the field according to the board topology.
+    /// Returns `None` for positions off a walled board.
+    pub fn wrap_point(&self, pos: Point2D<i32>) -> Option<Point2D<i32>> {
+        self.topology.wrap(pos, Point2D::new(self.field_size.x as i32, self.field_size.y as i32))
+    }
+
+    pub fn neighbor_point(&self, pos: Point2D<i32>, direction: Direction) -> Option<Point2D<i32>> {
+        self.wrap_point(pos + direction.offset())
+    }
+
+    pub fn neighbor_index(&self, index: usize, direction: Direction) -> Option<usize> {
+        self.neighbor_point(self.get_point_from_index(index), direction)
+            .map(|pos| self.get_index_from_point(pos))
+    }
+
+    /// Seconds between two game steps at the current progress of the round.
+    pub fn tick_interval(&self) -> f64 {
+        self.speed_curve.interval(self.stats.food_eaten)
+    }
+
+    /// Counts `dt` seconds into the round statistics while the game is being played.
+    pub fn add_play_time(&mut self, dt: f64) {
+        if self.state == GameState::Playing {
+            self.stats.time_played += dt;
+        }
+    }
+
+    /// Moves the game by one step. Returns the round statistics when this step ended the round.
+    pub fn update_game_state(&mut self) -> Option<RoundStats> {
+        match self.advance() {
+            StepOutcome::RoundOver(stats) => Some(stats),
+            _ => None,
+        }
+    }
+
+    /// Queues `input` (if any) and moves the game by one step.
+    pub fn step(&mut self, input: Option<Direction>) -> StepOutcome {
+        if let Some(direction) = input {
+            self.set_movement_direction(direction);
+        }
+
+        self.advance()
+    }
+
+    fn advance(&mut self) -> StepOutcome {
+        if self.state != GameState::Playing {
+            return StepOutcome::Idle;
+        }
+
+        for snake in self.snakes.iter_mut().filter(|snake| snake.is_alive()) {
+            if let Some(direction) = snake.input_queue.pop_front() {
+                if snake.is_turn_allowed(snake.direction, direction) {
+                    snake.direction = direction;
+                }
+            }
+        }
+
+        // all snakes move at once, first find out where every head goes and who dies on the way
+        let players = self.snakes.len();
+        let mut moves: Vec<Option<usize>> = vec![None; players];
+        let mut deaths: Vec<Option<GameOverType>> = vec![None; players];
+
+        for (player, snake) in self.snakes.iter().enumerate().filter(|(_, snake)| snake.is_alive()) {
+            match self.neighbor_index(snake.head_index, snake.direction) {
+                Some(index) => moves[player] = Some(index),
+                // leaving a walled field counts as a border hit even when the border ring is missing
+                None => deaths[player] = Some(GameOverType::BorderHit),
+            }
+        }
+
+        let grows: Vec<bool> = moves
+            .iter()
+            .map(|index| index.is_some_and(|index| self.field[index].cell_type == CellType::Food))
+            .collect();
+
+        // a tail leaves its cell in this step unless the snake grows, so a head may enter it
+        let leaving_tail = |owner: usize| match (moves[owner], grows[owner]) {
+            (Some(_), false) => self.snakes[owner].body.back().copied(),
+            _ => None,
         };
 
-        // println!(
-        //     "Game Update: Direction: {:?}, head_pos: {:?} -> {:?}",
-        //     self.direction,
-        //     head_pos_2d,
-        //     new_head_pos_2d,
-        // );
+        for (player, snake) in self.snakes.iter().enumerate() {
+            let new_head_index = match moves[player] {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let head_on = (0..players).filter(|other| *other != player).any(|other| {
+                moves[other] == Some(new_head_index)
+                    || (moves[other] == Some(snake.head_index) && new_head_index == self.snakes[other].head_index)
+            });
+
+            deaths[player] = match self.field[new_head_index].cell_type {
+                _ if head_on => Some(GameOverType::HeadOn),
+                CellType::Border => Some(GameOverType::BorderHit),
+                CellType::Snake(owner, _) if leaving_tail(owner) == Some(new_head_index) => None,
+                CellType::Snake(owner, _) if owner == player => Some(GameOverType::SelfBite),
+                CellType::Snake(_, _) => Some(GameOverType::SnakeHit),
+                _ => None,
+            };
+        }
+
+        let survivors = (0..players)
+            .filter(|player| self.snakes[*player].is_alive() && deaths[*player].is_none())
+            .count();
+
+        for (snake, death) in self.snakes.iter_mut().zip(&deaths) {
+            if death.is_some() {
+                snake.death = *death;
+            }
+        }
+
+        // a lone snake plays until it dies, several until at most one is left
+        let first_death = deaths.iter().flatten().next().copied();
+        if let Some(game_over_type) = first_death {
+            if survivors == 0 || (players > 1 && survivors == 1) {
+                return StepOutcome::RoundOver(self.end_round(game_over_type));
+            }
+        }
+
+        // dead snakes leave the field
+        for player in (0..players).filter(|player| deaths[*player].is_some()) {
+            for index in self.snakes[player].body.drain(..) {
+                self.field[index].cell_type = CellType::Empty;
+            }
+        }
+
+        // move snake bodies, tails first so a head can take over a cell left in the same step
+        for player in (0..players).filter(|player| deaths[*player].is_none() && !grows[*player]) {
+            if moves[player].is_some() {
+                if let Some(tail_index) = self.snakes[player].body.pop_back() {
+                    self.field[tail_index].cell_type = CellType::Empty;
+                }
+            }
+        }
+
+        for player in (0..players).filter(|player| deaths[*player].is_none()) {
+            if let Some(new_head_index) = moves[player] {
+                let snake = &mut self.snakes[player];
+                snake.body.push_front(new_head_index);
+                snake.head_index = new_head_index;
+            }
+        }
+
+        self.update_snake_cells();
 
-        // todo: collision (border hit, self bite, apple)
-        if new_head_pos_2d.x < 0 {
-            new_head_pos_2d.x = 0;
+        self.step_count += 1;
+
+        let mut ate = false;
+        for player in (0..players).filter(|player| deaths[*player].is_none() && grows[*player]) {
+            self.snakes[player].score.record_meal(&self.score_rules, self.step_count);
+            self.stats.food_eaten += 1;
+            ate = true;
         }
-        
-        if new_head_pos_2d.y < 0 {
-            new_head_pos_2d.y = 0;
+
+        if ate {
+            self.spawn_food();
         }
 
-        // debug
-        let new_head_index = self.get_index_from_point(new_head_pos_2d);
-        if new_head_index >= self.field.len() {
-            new_head_pos_2d = Point2D::new(0, 0);
+        let max_length = self.snakes.iter().map(Snake::len).max().unwrap_or_default();
+        self.stats.max_length = self.stats.max_length.max(max_length);
+
+        if !self.has_free_cell() {
+            return StepOutcome::RoundOver(self.end_round(GameOverType::PlaygroundFilled));
+        }
+
+        if ate {
+            StepOutcome::Ate
+        } else {
+            StepOutcome::Moved
         }
+    }
 
-        // move snake body
-        let new_head_cell = self.field
-            .get(self.get_index_from_point(new_head_pos_2d))
-            .unwrap();
+    fn end_round(&mut self, game_over_type: GameOverType) -> RoundStats {
+        self.state = GameState::GameOver(game_over_type);
+        self.stats.score = self.snakes.iter().map(|snake| snake.score.points).sum();
+        self.stats.steps = self.step_count;
+        self.stats.game_over = Some(game_over_type);
+        self.stats
+    }
 
-        new_head_cell.borrow_mut().cell_type = CellType::Snake(SnakeBodyPart::Head(1));
-        self.field.get(self.snake_head_index).unwrap().borrow_mut().cell_type = CellType::Empty;
+    fn update_snake_cells(&mut self) {
+        for (owner, snake) in self.snakes.iter().enumerate() {
+            let last_ordinal = snake.body.len();
 
-        self.snake_head_index = self.get_index_from_point(new_head_pos_2d);
+            for (i, index) in snake.body.iter().enumerate() {
+                let ordinal = i + 1;
+                let body_part = match ordinal {
+                    1 => SnakeBodyPart::Head(ordinal),
+                    o if o == last_ordinal => SnakeBodyPart::Tail(ordinal),
+                    _ => SnakeBodyPart::Body(ordinal),
+                };
+                self.field[*index].cell_type = CellType::Snake(owner, body_part);
+            }
+        }
+    }
+
+    fn has_free_cell(&self) -> bool {
+        self.field
+            .iter()
+            .any(|cell| matches!(cell.cell_type, CellType::Empty | CellType::Food))
     }
 }
 
@@ -269,24 +736,20 @@ pub struct CellIterator<'a> {
     iter_index: usize,
 }
 
-pub struct CellContext {
-    pub cell: Rc<RefCell<Cell>>,
-    pub cell_position: Point2D<Scalar>,
+pub struct CellContext<'a> {
+    pub cell: &'a Cell,
+    pub cell_position: Point2D<f64>,
 }
 
 impl<'a> Iterator for CellIterator<'a> {
-    type Item = CellContext;
+    type Item = CellContext<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.iter_index >= self.game.field.len() {
-            return None;
-        }
-
-        let cell = self.game.field[self.iter_index].clone();
+        let cell = self.game.field.get(self.iter_index)?;
         let cell_pos = self.game.get_point_from_index(self.iter_index);
         let cell_context = CellContext {
             cell,
-            cell_position: Point2D::new(cell_pos.x as Scalar, cell_pos.y as Scalar),
+            cell_position: Point2D::new(cell_pos.x as f64, cell_pos.y as f64),
         };
 
         // move to the next cell
@@ -296,3 +759,378 @@ impl<'a> Iterator for CellIterator<'a> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FarFromHead;
+
+    /// Removes all food from the field.
+    fn clear_food(game: &mut Game) {
+        for cell in game.field.iter_mut().filter(|cell| cell.cell_type == CellType::Food) {
+            cell.cell_type = CellType::Empty;
+        }
+    }
+
+    /// Puts food right in front of player one and steps onto it.
+    fn feed(game: &mut Game) {
+        let snake = game.snake();
+        let ahead = game.neighbor_index(snake.head_index, snake.direction).unwrap();
+        game.field[ahead].cell_type = CellType::Food;
+        assert!(matches!(game.step(None), StepOutcome::Ate));
+    }
+
+    /// Snake of length 3 in a 10x10 walled field, heading right with the head at (7, 5).
+    fn game_with_snake_of_length_3() -> Game {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        game.play(Direction::Right);
+        feed(&mut game);
+        feed(&mut game);
+        clear_food(&mut game);
+        game
+    }
+
+    /// Snake of `length` segments lying along a row, heading right.
+    fn snake_of_length(length: usize) -> Snake {
+        let mut snake = Snake::new(length + 10, Direction::Right);
+        snake.body = (11..length + 11).rev().collect();
+        snake
+    }
+
+    #[test]
+    fn lone_head_turns_anywhere() {
+        let snake = snake_of_length(1);
+
+        for to in [Direction::Left, Direction::Right, Direction::Up, Direction::Down] {
+            assert!(snake.is_turn_allowed(Direction::Right, to));
+        }
+    }
+
+    #[test]
+    fn two_segments_do_not_reverse_into_the_neck() {
+        let snake = snake_of_length(2);
+
+        assert!(!snake.is_turn_allowed(Direction::Right, Direction::Left));
+        assert!(!snake.is_turn_allowed(Direction::Up, Direction::Down));
+        assert!(snake.is_turn_allowed(Direction::Right, Direction::Up));
+        assert!(snake.is_turn_allowed(Direction::Right, Direction::Down));
+        assert!(snake.is_turn_allowed(Direction::Right, Direction::Right));
+    }
+
+    #[test]
+    fn long_snake_does_not_reverse_into_its_body() {
+        let snake = snake_of_length(5);
+
+        assert!(!snake.is_turn_allowed(Direction::Right, Direction::Left));
+        assert!(!snake.is_turn_allowed(Direction::Left, Direction::Right));
+        assert!(!snake.is_turn_allowed(Direction::Down, Direction::Up));
+        assert!(snake.is_turn_allowed(Direction::Left, Direction::Up));
+        assert!(snake.is_turn_allowed(Direction::Down, Direction::Left));
+    }
+
+    #[test]
+    fn ignored_reversal_keeps_going_straight() {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        game.play(Direction::Right);
+
+        let head = game.snake().head_index;
+        game.field[head + 1].cell_type = CellType::Food;
+        assert!(matches!(game.step(None), StepOutcome::Ate));
+        assert_eq!(game.snake().len(), 2);
+
+        game.step(Some(Direction::Left));
+
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.snake().direction, Direction::Right);
+        assert_eq!(game.snake().head_index, head + 2);
+        assert_eq!(game.snake().len(), 2);
+    }
+
+    #[test]
+    fn snake_cells_of_other_players_differ() {
+        let head = |owner| CellType::Snake(owner, SnakeBodyPart::Head(1));
+
+        assert_eq!(head(0), head(0));
+        assert_ne!(head(0), head(1));
+        assert_ne!(head(0), CellType::Snake(0, SnakeBodyPart::Body(1)));
+    }
+
+    #[test]
+    fn food_keeps_away_from_living_heads_only() {
+        let mut game = Game::with_players(12, 12, BoardTopology::Walled, 3, 1).unwrap();
+        for index in game.snakes[0].body.drain(..) {
+            game.field[index].cell_type = CellType::Empty;
+        }
+        game.snakes[0].death = Some(GameOverType::BorderHit);
+        for cell in game.field.iter_mut().filter(|cell| cell.cell_type == CellType::Food) {
+            cell.cell_type = CellType::Empty;
+        }
+
+        game.set_food_spawner(FoodSpawner::new(Box::new(FarFromHead), 1));
+
+        let heads: Vec<Point2D<i32>> = game.snakes[1..].iter().map(|snake| game.get_point_from_index(snake.head_index)).collect();
+        let distance = |index: usize| {
+            let pos = game.get_point_from_index(index);
+            heads.iter().map(|head| (pos.x - head.x).abs() + (pos.y - head.y).abs()).min().unwrap()
+        };
+        let food = game.field.iter().position(|cell| cell.cell_type == CellType::Food).unwrap();
+        let farthest = (0..game.field.len())
+            .filter(|index| matches!(game.field[*index].cell_type, CellType::Empty | CellType::Food))
+            .map(distance)
+            .max();
+
+        assert_eq!(Some(distance(food)), farthest);
+    }
+
+    #[test]
+    fn turns_within_one_step_apply_on_consecutive_steps() {
+        let mut game = game_with_snake_of_length_3();
+
+        game.set_movement_direction(Direction::Up);
+        game.set_movement_direction(Direction::Left);
+
+        game.step(None);
+        assert_eq!(game.snake().direction, Direction::Up);
+        assert_eq!(game.snake().head_index, game.get_field_index(7, 4));
+
+        game.step(None);
+        assert_eq!(game.snake().direction, Direction::Left);
+        assert_eq!(game.snake().head_index, game.get_field_index(6, 4));
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn quick_double_turn_does_not_reverse_into_the_body() {
+        let mut game = game_with_snake_of_length_3();
+
+        // down is checked against the queued up, not against the current right
+        game.set_movement_direction(Direction::Up);
+        game.set_movement_direction(Direction::Down);
+        assert_eq!(game.snake().input_queue, [Direction::Up]);
+
+        game.step(None);
+        game.step(None);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.snake().direction, Direction::Up);
+        assert_eq!(game.snake().head_index, game.get_field_index(7, 3));
+    }
+
+    #[test]
+    fn input_queue_is_limited() {
+        let mut game = game_with_snake_of_length_3();
+
+        for direction in [Direction::Up, Direction::Left, Direction::Down, Direction::Right] {
+            game.set_movement_direction(direction);
+        }
+
+        assert_eq!(INPUT_QUEUE_CAPACITY, 3);
+        assert_eq!(game.snake().input_queue, [Direction::Up, Direction::Left, Direction::Down]);
+    }
+
+    fn cell_types(game: &Game) -> Vec<CellType> {
+        game.field.iter().map(|cell| cell.cell_type).collect()
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_game() {
+        let mut games = [
+            Game::new(8, 8, BoardTopology::Wrap, 21).unwrap(),
+            Game::new(8, 8, BoardTopology::Wrap, 21).unwrap(),
+        ];
+        let mut input_rng = GameRng::new(4);
+        let directions = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+        for game in games.iter_mut() {
+            game.play(Direction::Right);
+        }
+        for _ in 0..300 {
+            let input = directions[(input_rng.next_u32() % 4) as usize];
+            for game in games.iter_mut() {
+                if let StepOutcome::RoundOver(_) = game.step(Some(input)) {
+                    game.new_round();
+                    game.play(Direction::Right);
+                }
+            }
+
+            assert_eq!(cell_types(&games[0]), cell_types(&games[1]));
+            assert_eq!(games[0].rng, games[1].rng);
+        }
+
+        assert!(games[0].round > 0);
+        assert_eq!((games[0].seed, games[0].step_count), (games[1].seed, games[1].step_count));
+    }
+
+    #[test]
+    fn restart_repeats_the_round_and_new_round_moves_on() {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 8).unwrap();
+        let first_round = cell_types(&game);
+        game.play(Direction::Up);
+        game.step(None);
+        game.step(None);
+
+        game.restart();
+        assert_eq!(cell_types(&game), first_round);
+        assert_eq!((game.seed, game.round, game.step_count), (8, 0, 0));
+        assert_eq!(game.state, GameState::Paused);
+
+        // the next seed comes from the generator of the restarted round, as it would from a fresh game
+        let mut fresh = Game::new(10, 10, BoardTopology::Walled, 8).unwrap();
+        fresh.new_round();
+        game.new_round();
+        assert_ne!(game.seed, 8);
+        assert_eq!((game.seed, game.round), (fresh.seed, 1));
+        assert_eq!(cell_types(&game), cell_types(&Game::new(10, 10, BoardTopology::Walled, game.seed).unwrap()));
+    }
+
+    #[test]
+    fn leaving_the_field_is_a_border_hit() {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        clear_food(&mut game);
+        game.play(Direction::Up);
+
+        for _ in 0..4 {
+            assert!(matches!(game.step(None), StepOutcome::Moved));
+        }
+        let stats = match game.step(None) {
+            StepOutcome::RoundOver(stats) => stats,
+            outcome => panic!("expected the round to end, got {:?}", outcome),
+        };
+
+        // the fatal step is not counted and the head stays in front of the wall
+        assert_eq!(game.state, GameState::GameOver(GameOverType::BorderHit));
+        assert_eq!((stats.game_over, stats.steps), (Some(GameOverType::BorderHit), 4));
+        assert_eq!(game.snake().head_index, game.get_field_index(5, 1));
+        assert!(matches!(game.step(None), StepOutcome::Idle));
+    }
+
+    #[test]
+    fn running_into_the_body_is_a_self_bite() {
+        let mut game = Game::new(12, 12, BoardTopology::Walled, 1).unwrap();
+        game.play(Direction::Right);
+        for _ in 0..4 {
+            feed(&mut game);
+        }
+        clear_food(&mut game);
+
+        game.step(Some(Direction::Up));
+        game.step(Some(Direction::Left));
+        let outcome = game.step(Some(Direction::Down));
+
+        assert!(matches!(outcome, StepOutcome::RoundOver(_)));
+        assert_eq!(game.state, GameState::GameOver(GameOverType::SelfBite));
+    }
+
+    #[test]
+    fn head_may_follow_the_tail() {
+        let mut game = Game::new(12, 12, BoardTopology::Walled, 1).unwrap();
+        game.play(Direction::Right);
+        for _ in 0..3 {
+            feed(&mut game);
+        }
+        clear_food(&mut game);
+
+        // a snake of 4 turning in a square enters the cell its tail leaves in the same step
+        let tail = *game.snake().body.back().unwrap();
+        game.step(Some(Direction::Up));
+        game.step(Some(Direction::Left));
+        assert!(matches!(game.step(Some(Direction::Down)), StepOutcome::Moved));
+        assert_eq!(game.snake().head_index, tail + 2);
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn filling_the_field_ends_the_round() {
+        let mut game = Game::new(5, 5, BoardTopology::Walled, 1).unwrap();
+        clear_food(&mut game);
+
+        // only the head at (2, 2) and the food right of it are left inside the walls
+        let food = game.get_field_index(3, 2);
+        for (index, cell) in game.field.iter_mut().enumerate() {
+            if cell.cell_type == CellType::Empty {
+                cell.cell_type = if index == food { CellType::Food } else { CellType::Border };
+            }
+        }
+        game.play(Direction::Right);
+
+        let stats = match game.step(None) {
+            StepOutcome::RoundOver(stats) => stats,
+            outcome => panic!("expected the round to end, got {:?}", outcome),
+        };
+        assert_eq!(game.state, GameState::GameOver(GameOverType::PlaygroundFilled));
+        assert_eq!((stats.food_eaten, stats.steps, stats.max_length, stats.score), (1, 1, 2, 10));
+    }
+
+    #[test]
+    fn snake_grows_by_eating_and_numbers_its_parts() {
+        let mut game = Game::new(12, 12, BoardTopology::Walled, 1).unwrap();
+        game.play(Direction::Right);
+        for _ in 0..3 {
+            feed(&mut game);
+        }
+        clear_food(&mut game);
+
+        let body = game.snake().body.clone();
+        assert_eq!(body, [9, 8, 7, 6].map(|x| game.get_field_index(x, 6)));
+        assert_eq!(game.stats.max_length, 4);
+
+        let parts: Vec<SnakeBodyPart> = body
+            .iter()
+            .map(|index| match game.field[*index].cell_type {
+                CellType::Snake(0, part) => part,
+                cell_type => panic!("expected a snake cell, got {:?}", cell_type),
+            })
+            .collect();
+        assert!(matches!(
+            parts[..],
+            [SnakeBodyPart::Head(1), SnakeBodyPart::Body(2), SnakeBodyPart::Body(3), SnakeBodyPart::Tail(4)]
+        ));
+
+        // without food the snake keeps its length and frees the cell of its tail
+        game.step(None);
+        assert_eq!(game.snake().len(), 4);
+        assert_eq!(game.field[body[3]].cell_type, CellType::Empty);
+        assert!(matches!(game.field[body[2]].cell_type, CellType::Snake(0, SnakeBodyPart::Tail(4))));
+    }
+
+    #[test]
+    fn paused_game_stands_still() {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        assert_eq!(game.state, GameState::Paused);
+        assert!(matches!(game.step(Some(Direction::Up)), StepOutcome::Idle));
+
+        game.play(Direction::Right);
+        game.step(None);
+        game.pause();
+        let (head, steps) = (game.snake().head_index, game.step_count);
+
+        assert!(matches!(game.step(None), StepOutcome::Idle));
+        assert!(game.update_game_state().is_none());
+        assert_eq!((game.snake().head_index, game.step_count), (head, steps));
+
+        game.toggle_pause();
+        assert_eq!(game.state, GameState::Playing);
+        assert!(!matches!(game.step(None), StepOutcome::Idle));
+        assert_eq!(game.step_count, steps + 1);
+    }
+
+    #[test]
+    fn wrapping_field_reenters_from_the_opposite_edge() {
+        let mut game = Game::new(8, 6, BoardTopology::Wrap, 1).unwrap();
+        clear_food(&mut game);
+        assert!(game.field.iter().all(|cell| cell.cell_type != CellType::Border));
+
+        // right from (4, 3) over the right edge, then up over the top edge
+        game.play(Direction::Right);
+        for _ in 0..4 {
+            game.step(None);
+        }
+        assert_eq!(game.snake().head_index, game.get_field_index(0, 3));
+
+        game.set_movement_direction(Direction::Up);
+        for _ in 0..4 {
+            game.step(None);
+        }
+        assert_eq!(game.snake().head_index, game.get_field_index(0, 5));
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.neighbor_index(game.get_field_index(0, 5), Direction::Left), Some(game.get_field_index(7, 5)));
+    }
+}
@@ -5,38 +5,45 @@ extern crate piston;
 
 use glutin_window::GlutinWindow as Window;
 use graphics::math::Scalar;
-use graphics::rectangle;
 use opengl_graphics::{GlGraphics, OpenGL, Texture, TextureSettings};
 use piston::event_loop::{EventSettings, Events};
 use piston::input::{RenderArgs, RenderEvent, UpdateArgs, UpdateEvent};
 use piston::window::WindowSettings;
 use piston::Button::Keyboard;
-use piston::{ButtonEvent, Key};
+use piston::{ButtonEvent, ButtonState, Key};
+
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use my_snake::*;
-use my_snake::game_state::GameStateX;
-use my_snake::point_2d::Point2D;
+use my_snake::autopilot::Autopilot;
+use my_snake::controller::{Controller, KeyboardController, ScriptedController};
+use my_snake::error::SnakeError;
+use my_snake::hamiltonian::HamiltonianSolver;
+use my_snake::level::Level;
+use my_snake::obstacles::ObstacleGenerator;
+use my_snake::save;
+use my_snake::replay::{Replay, ReplayPlayer, ReplayRecorder};
+use my_snake::score::RoundStats;
+use my_snake::ticker::Ticker;
 
 struct App {
     gl: GlGraphics,
     t: f64,
     dt: f64,
-    initial_update_game_state_cooldown: f64,
-    update_game_state_cooldown: f64,
+    ticker: Ticker,
 }
 
 impl App {
-    fn new(gl: GlGraphics, initial_update_game_state_cooldown: f64) -> App {
+    fn new(gl: GlGraphics) -> App {
         App {
             gl,
             t: 0.0,
             dt: 0.0,
-            initial_update_game_state_cooldown,
-            update_game_state_cooldown: initial_update_game_state_cooldown
+            ticker: Ticker::default(),
         }
     }
     
-    fn render(&mut self, args: &RenderArgs, render_settings: &RenderSettings, game: &mut Game, texture_grass: &Texture) {
+    fn render(&mut self, args: &RenderArgs, render_settings: &RenderSettings, game: &Game, texture_grass: &Texture) {
         use graphics::*;
 
         const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
@@ -45,12 +52,16 @@ impl App {
         const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
         const GREEN_DARK: [f32; 4] = [0.0, 0.75, 0.0, 1.0];
         const DARK: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
-        const GRAY: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
         const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
-        const BLUE: [f32; 4] = [0.2, 0.0, 1.0, 1.0];
         const YELLOW: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
         const MAGENTA: [f32; 4] = [1.0, 0.0, 0.9, 1.0];
 
+        // head, body and tail colors of every player's snake
+        const SNAKE_COLORS: [[[f32; 4]; 3]; 2] = [
+            [GREEN, YELLOW, GREEN_DARK],
+            [[0.0, 0.8, 1.0, 1.0], [0.3, 0.4, 1.0, 1.0], [0.0, 0.5, 0.7, 1.0]],
+        ];
+
         self.gl.draw(args.viewport(), |c, gl| {
             // Clear the screen.
             clear(BLACK, gl);
@@ -81,8 +92,8 @@ impl App {
             );
 
             // println!("frame...");
-            for cell_context in game.cell_iter_mut() {
-                let mut cell = cell_context.cell.borrow_mut();
+            for cell_context in game.cell_iter() {
+                let cell = cell_context.cell;
                 let transform = c.transform.trans(
                     cell_context.cell_position.x * render_settings.square_size.x,
                     cell_context.cell_position.y * render_settings.square_size.y,
@@ -92,10 +103,13 @@ impl App {
                     CellType::Uninitialized => MAGENTA,
                     CellType::Empty => DARK,
                     CellType::Border => BROWN,
-                    CellType::Snake(snake_body_part) => match snake_body_part {
-                        SnakeBodyPart::Head(_) => GREEN,
-                        SnakeBodyPart::Body(_) => YELLOW,
-                        SnakeBodyPart::Tail(_) => GREEN_DARK,
+                    CellType::Snake(owner, snake_body_part) => {
+                        let [head, body, tail] = SNAKE_COLORS[owner % SNAKE_COLORS.len()];
+                        match snake_body_part {
+                            SnakeBodyPart::Head(_) => head,
+                            SnakeBodyPart::Body(_) => body,
+                            SnakeBodyPart::Tail(_) => tail,
+                        }
                     },
                     CellType::Food => RED,
                 };
@@ -107,58 +121,212 @@ impl App {
         });
     }
     
-    fn start(&mut self) {
-        self.update_game_state_cooldown = self.initial_update_game_state_cooldown;
+    fn start(&mut self, game: &Game) {
+        self.ticker.reset(game);
     }
 
-    fn update(&mut self, args: &UpdateArgs, game: &mut Game) {
+    fn update(&mut self, args: &UpdateArgs, game: &mut Game, before_step: impl FnOnce(&mut Game)) -> Option<RoundStats> {
         self.t += args.dt;
         self.dt = args.dt;
-        
-        self.update_game_state_cooldown -= self.dt;
-        if self.update_game_state_cooldown <= 0.0 {
-            game.update_game_state();
-            self.update_game_state_cooldown = self.initial_update_game_state_cooldown;
-        }
+
+        self.ticker.update_with(self.dt, game, before_step)
     }
 }
 
-fn main() {
+/// Playback state of a replay loaded with `--replay <file>`.
+struct Playback {
+    player: ReplayPlayer,
+    paused: bool,
+    fast_forward: bool,
+}
 
-    let board_size = Point2D::new(3, 3);
-    
-    let mut game_state = match GameStateX::new(board_size) {
-        Ok(game_state) => game_state,
+const FAST_FORWARD_SPEED: f64 = 4.0;
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    args.next()
+}
+
+fn load_replay(path: &str) -> Replay {
+    let replay = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| text.parse::<Replay>().map_err(|e| e.to_string()));
+
+    match replay {
+        Ok(replay) => replay,
         Err(e) => {
-            eprintln!("Failed to initialize game: {}", e);
+            eprintln!("Failed to load replay {}: {}", path, e);
+            std::process::exit(1);
+        },
+    }
+}
+
+fn save_replay(path: &str, replay: &Replay) {
+    match std::fs::write(path, replay.to_string()) {
+        Ok(()) => println!("Replay saved to {}", path),
+        Err(e) => eprintln!("Failed to save replay {}: {}", path, e),
+    }
+}
+
+/// Bundled level by name, or a level file.
+fn load_level(name: &str) -> Level {
+    if let Some(level) = Level::bundled(name) {
+        return level;
+    }
+
+    let level = std::fs::read_to_string(name)
+        .map_err(|e| e.to_string())
+        .and_then(|text| Level::parse(&text).map_err(|e| e.to_string()));
+
+    match level {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("Failed to load level {}: {}", name, e);
             std::process::exit(1);
+        },
+    }
+}
+
+fn load_script(path: &str) -> ScriptedController {
+    let script = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| text.parse::<ScriptedController>().map_err(|e| e.to_string()));
+
+    match script {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Failed to load script {}: {}", path, e);
+            std::process::exit(1);
+        },
+    }
+}
+
+/// Computer or scripted player picked on the command line, the keyboard when none is.
+fn controller_from_args(game: &Game) -> Option<Box<dyn Controller>> {
+    if let Some(path) = arg_value("--script") {
+        return Some(Box::new(load_script(&path)));
+    }
+
+    if std::env::args().any(|arg| arg == "--solver") {
+        match HamiltonianSolver::new(game) {
+            Ok(solver) => return Some(Box::new(solver)),
+            Err(e) => eprintln!("Solver not available: {}", e),
         }
+    }
+
+    if std::env::args().any(|arg| arg == "--autopilot") {
+        return Some(Box::new(Autopilot::new()));
+    }
+
+    None
+}
+
+const DEFAULT_SAVE_PATH: &str = "my-snake-save.json";
+
+/// Saves as JSON, or in the compact binary format when the path ends with `.bin`.
+fn save_game(path: &str, game: &Game) {
+    let result = if path.ends_with(".bin") {
+        save::to_binary(game).map_err(|e| e.to_string())
+            .and_then(|bytes| std::fs::write(path, bytes).map_err(|e| e.to_string()))
+    } else {
+        save::to_json(game).map_err(|e| e.to_string())
+            .and_then(|text| std::fs::write(path, text).map_err(|e| e.to_string()))
     };
 
-    println!("cells");
-    for c in game_state.board.data.iter() {
-        println!("{:?}", c);
+    match result {
+        Ok(()) => println!("Game saved to {}", path),
+        Err(e) => eprintln!("Failed to save game {}: {}", path, e),
     }
-    
-    println!("cells #2");
+}
 
-    for i in 0..game_state.board.data.len() {
-        game_state.board.get_mut(i).unwrap().pos.x = i as i32;
+fn load_game(path: &str) -> Option<Game> {
+    let result = if path.ends_with(".bin") {
+        std::fs::read(path).map_err(|e| e.to_string())
+            .and_then(|bytes| save::from_binary(&bytes).map_err(|e| e.to_string()))
+    } else {
+        std::fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|text| save::from_json(&text).map_err(|e| e.to_string()))
+    };
+
+    match result {
+        Ok(game) => Some(game),
+        Err(e) => {
+            eprintln!("Failed to load game {}: {}", path, e);
+            None
+        },
     }
+}
+
+fn main() {
+
+    let (cols, rows) = (12, 12);
+    let topology = if std::env::args().any(|arg| arg == "--wrap") {
+        BoardTopology::Wrap
+    } else {
+        BoardTopology::Walled
+    };
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    println!("seed: {}", seed);
+
+    let record_path = arg_value("--record");
+    let save_path = arg_value("--save-file").unwrap_or_else(|| DEFAULT_SAVE_PATH.to_string());
+    let mut playback = arg_value("--replay").map(|path| Playback {
+        player: ReplayPlayer::new(load_replay(&path)),
+        paused: false,
+        fast_forward: false,
+    });
 
-    for c in game_state.board.data.iter() {
-        println!("{:?}", c);
+    let players = match arg_value("--players").map(|players| players.parse::<usize>()) {
+        Some(Ok(players)) => players,
+        Some(Err(e)) => {
+            eprintln!("Invalid player count: {}", e);
+            std::process::exit(1);
+        },
+        None => 1,
+    };
+
+    // levels and replays have a single spawn point, the other snakes would be dropped
+    if players > 1 {
+        if let Some(option) = ["--level", "--obstacles", "--replay"].into_iter().find(|option| arg_value(option).is_some()) {
+            eprintln!("{}", SnakeError::SinglePlayerOnly { feature: option.to_string(), players });
+            std::process::exit(1);
+        }
     }
 
-    println!("cells #3");
-    for c in game_state.iter_cell_mut() {
-        println!("c: {:?} = {:?}", c.cell_index, c.cell);
+    let mut level = arg_value("--level").map(|name| load_level(&name));
+
+    if let Some(density) = arg_value("--obstacles") {
+        let density = match density.parse::<f64>() {
+            Ok(density) => density,
+            Err(e) => {
+                eprintln!("Invalid obstacle density {}: {}", density, e);
+                std::process::exit(1);
+            },
+        };
+        let base = match level.take() {
+            Some(level) => Ok(level),
+            None => Level::open(cols, rows, topology),
+        };
+        level = match base {
+            Ok(base) => Some(ObstacleGenerator::new(density, seed).generate(&base)),
+            Err(e) => {
+                eprintln!("Failed to initialize game: {}", e);
+                std::process::exit(1);
+            },
+        };
     }
 
-    panic!("END!");
+    let game = match (&playback, level) {
+        (Some(playback), _) => playback.player.replay().new_game(),
+        (None, Some(level)) => Game::from_level(level, seed),
+        (None, None) => Game::with_players(cols, rows, topology, players, seed),
+    };
 
-    let (cols, rows) = (12, 12);
-    let mut game = match Game::new(cols, rows) {
+    let mut game = match game {
         Ok(game) => game,
         Err(e) => {
             eprintln!("Failed to initialize game: {}", e);
@@ -166,6 +334,23 @@ fn main() {
         },
     };
 
+    // replays only know the board size and player one's inputs
+    let record_path = match (record_path, &game.level, game.snakes.len()) {
+        (Some(_), Some(_), _) => {
+            eprintln!("Recording replays of levels is not supported, not recording");
+            None
+        },
+        (Some(_), _, 2..) => {
+            eprintln!("Recording replays of several players is not supported, not recording");
+            None
+        },
+        (record_path, _, _) => record_path,
+    };
+    let mut recorder = record_path.as_ref().map(|_| ReplayRecorder::new(&game));
+    let mut keyboard = KeyboardController::new();
+    let mut keyboard_two = KeyboardController::for_player(1);
+    let mut computer = controller_from_args(&game);
+
     let render_settings = RenderSettings::new(
         [1000, 1000],
         game.field_size.as_array(),
@@ -182,10 +367,9 @@ fn main() {
         .unwrap();
 
     // Create a new game and run it.
-    let mut app = App::new(
-        GlGraphics::new(opengl),
-        0.5
-    );
+    let mut app = App::new(GlGraphics::new(opengl));
+
+    app.start(&game);
 
     let mut events = Events::new(EventSettings::new());
 
@@ -196,25 +380,129 @@ fn main() {
 
     while let Some(e) = events.next(&mut window) {
         if let Some(args) = e.button_args() {
-            match args.button {
-                Keyboard(key) => match key {
-                    Key::Up => game.set_movement_direction(Direction::Up),
-                    Key::Down => game.set_movement_direction(Direction::Down),
-                    Key::Left => game.set_movement_direction(Direction::Left),
-                    Key::Right => game.set_movement_direction(Direction::Right),
-                    _ => {},
-                },
-                _ => {},
+            if let (Keyboard(key), ButtonState::Press) = (args.button, args.state) {
+                let direction = match key {
+                    Key::Up => Some(Direction::Up),
+                    Key::Down => Some(Direction::Down),
+                    Key::Left => Some(Direction::Left),
+                    Key::Right => Some(Direction::Right),
+                    _ => None,
+                };
+                // WASD steers player two
+                let direction_two = match key {
+                    _ if game.snakes.len() < 2 => None,
+                    Key::W => Some(Direction::Up),
+                    Key::S => Some(Direction::Down),
+                    Key::A => Some(Direction::Left),
+                    Key::D => Some(Direction::Right),
+                    _ => None,
+                };
+
+                if let Some(playback) = playback.as_mut() {
+                    // Space: pause, Period: single step while paused, F: fast forward, R: from the start
+                    match key {
+                        Key::Space => playback.paused = !playback.paused,
+                        Key::Period if playback.paused => {
+                            playback.player.step(&mut game);
+                        },
+                        Key::F => playback.fast_forward = !playback.fast_forward,
+                        Key::R => {
+                            if let Ok(new_game) = playback.player.restart() {
+                                game = new_game;
+                                app.start(&game);
+                            }
+                        },
+                        _ => {},
+                    }
+                } else {
+                    // arrows: player one, WASD: player two, P: toggle the autopilot
+                    match (key, direction.or(direction_two), game.state, recorder.as_mut()) {
+                        (_, Some(direction), GameState::Paused, Some(recorder)) => recorder.play(&mut game, direction),
+                        (_, Some(direction), GameState::Paused, None) if direction_two.is_some() => {
+                            game.play_player(1, direction);
+                        },
+                        (_, Some(direction), GameState::Paused, None) => game.play(direction),
+                        (_, Some(direction), _, _) if direction_two.is_some() => keyboard_two.press(direction),
+                        (_, Some(direction), _, _) => keyboard.press(direction),
+                        (Key::Space, _, _, _) => game.toggle_pause(),
+                        (Key::P, _, _, _) => {
+                            computer = match computer {
+                                Some(_) => None,
+                                None => Some(Box::new(Autopilot::new())),
+                            };
+                            keyboard.clear();
+                            println!("Autopilot {}", if computer.is_some() { "on" } else { "off" });
+                        },
+                        (Key::R, _, _, _) => {
+                            game.new_round();
+                            app.start(&game);
+                            keyboard.clear();
+                            keyboard_two.clear();
+                            // scripts start over with every round
+                            if computer.is_some() {
+                                computer = controller_from_args(&game).or(computer);
+                            }
+                            if record_path.is_some() {
+                                recorder = Some(ReplayRecorder::new(&game));
+                            }
+                        },
+                        (Key::F5, _, _, _) => save_game(&save_path, &game),
+                        (Key::F9, _, _, _) => {
+                            if let Some(loaded) = load_game(&save_path) {
+                                game = loaded;
+                                game.pause();
+                                app.start(&game);
+
+                                // a replay cannot start in the middle of a round
+                                if recorder.take().is_some() {
+                                    println!("Recording continues with the next round");
+                                }
+                            }
+                        },
+                        _ => {},
+                    }
+                }
             }
-            println!("{:?}", args);
         }
 
         if let Some(args) = e.update_args() {
-            app.update(&args, &mut game);
+            if let Some(playback) = playback.as_mut() {
+                app.t += args.dt;
+
+                let speed = if playback.fast_forward { FAST_FORWARD_SPEED } else { 1.0 };
+                if !playback.paused && app.ticker.tick(args.dt * speed) {
+                    playback.player.step(&mut game);
+                    app.ticker.reset(&game);
+                }
+            } else if let Some(stats) = app.update(&args, &mut game, |game| {
+                let controller: &mut dyn Controller = match computer.as_deref_mut() {
+                    Some(computer) => computer,
+                    None => &mut keyboard,
+                };
+                let direction = controller.next_direction(game);
+                match (direction, recorder.as_mut()) {
+                    (Some(direction), Some(recorder)) => recorder.set_movement_direction(game, direction),
+                    (Some(direction), None) => game.set_player_direction(controller.player(), direction),
+                    (None, _) => {},
+                }
+
+                if let Some(direction) = keyboard_two.next_direction(game) {
+                    game.set_player_direction(keyboard_two.player, direction);
+                }
+            }) {
+                println!("Round over: {:?}", stats);
+                if let Some(winner) = game.winner() {
+                    println!("Player {} wins", winner + 1);
+                }
+
+                if let (Some(recorder), Some(path)) = (&recorder, &record_path) {
+                    save_replay(path, &recorder.finish(&game));
+                }
+            }
         }
         
         if let Some(args) = e.render_args() {
-            app.render(&args, &render_settings, &mut game, &tex_grass);
+            app.render(&args, &render_settings, &game, &tex_grass);
         }
     }
 }
@@ -1,36 +1,147 @@
+use std::ops::{Index, IndexMut};
+use crate::error::SnakeError;
 use crate::point_2d::Point2D;
+use serde::{Deserialize, Serialize};
 
+const NEIGHBORS_4: [Point2D<i32>; 4] = [
+    Point2D { x: 0, y: -1 },
+    Point2D { x: 1, y: 0 },
+    Point2D { x: 0, y: 1 },
+    Point2D { x: -1, y: 0 },
+];
+
+const NEIGHBORS_8: [Point2D<i32>; 8] = [
+    Point2D { x: 0, y: -1 },
+    Point2D { x: 1, y: -1 },
+    Point2D { x: 1, y: 0 },
+    Point2D { x: 1, y: 1 },
+    Point2D { x: 0, y: 1 },
+    Point2D { x: -1, y: 1 },
+    Point2D { x: -1, y: 0 },
+    Point2D { x: -1, y: -1 },
+];
+
+/// Rectangular grid stored row by row in a single container.
+/// `size.x` is the number of columns, `size.y` the number of rows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Matrix2DData<T>")]
 pub struct Matrix2D<T> {
-    // TODO: make private
-    pub size: Point2D<i32>,
-    pub data: Vec<T>,
+    size: Point2D<i32>,
+    data: Vec<T>,
 }
 
-impl<T: Default + Clone> Matrix2D<T> {
-    pub fn new(size: &Point2D<i32>) -> Result<Matrix2D<T>, String> {
-        if size.x <= 0 || size.y <= 0 {
-            return Err(format!("Invalid dimensions {}x{}", size.x, size.y));
+/// Deserialized form of `Matrix2D`, checked before it becomes one.
+#[derive(Deserialize)]
+struct Matrix2DData<T> {
+    size: Point2D<i32>,
+    data: Vec<T>,
+}
+
+impl<T> TryFrom<Matrix2DData<T>> for Matrix2D<T> {
+    type Error = String;
+
+    fn try_from(matrix: Matrix2DData<T>) -> Result<Self, Self::Error> {
+        let Matrix2DData { size, data } = matrix;
+        Self::check_size(&size).map_err(|e| e.to_string())?;
+
+        if data.len() as u64 != size.x as u64 * size.y as u64 {
+            return Err(format!("{}x{} matrix with {} cells", size.x, size.y, data.len()));
         }
 
+        Ok(Matrix2D { size, data })
+    }
+}
+
+impl<T: Default + Clone> Matrix2D<T> {
+    pub fn new(size: &Point2D<i32>) -> Result<Matrix2D<T>, SnakeError> {
+        Matrix2D::new_filled(size, T::default())
+    }
+}
+
+impl<T: Clone> Matrix2D<T> {
+    pub fn new_filled(size: &Point2D<i32>, value: T) -> Result<Matrix2D<T>, SnakeError> {
+        Self::check_size(size)?;
+
         let total_size = (size.x * size.y) as usize;
 
         Ok(Matrix2D {
             size: *size,
-            data: vec![T::default(); total_size],
+            data: vec![value; total_size],
         })
     }
 
+    pub fn fill(&mut self, value: T) {
+        self.data.fill(value);
+    }
+
+    /// Changes the size while keeping the content of the overlapping region.
+    /// New cells are set to `value`.
+    pub fn resize(&mut self, new_size: &Point2D<i32>, value: T) -> Result<(), SnakeError> {
+        let old = std::mem::replace(self, Matrix2D::new_filled(new_size, value)?);
+
+        for (pos, item) in old.iter_with_pos() {
+            if let Some(target) = self.get_pos_mut(pos) {
+                *target = item.clone();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Matrix2D<T> {
+    /// Builds the matrix from a function of the cell position.
+    pub fn from_fn<F>(size: &Point2D<i32>, mut f: F) -> Result<Matrix2D<T>, SnakeError>
+    where F: FnMut(Point2D<i32>) -> T {
+        Self::check_size(size)?;
+
+        let data = (0..size.x * size.y)
+            .map(|i| f(Point2D::new(i % size.x, i / size.x)))
+            .collect();
+
+        Ok(Matrix2D { size: *size, data })
+    }
+
+    fn check_size(size: &Point2D<i32>) -> Result<(), SnakeError> {
+        if size.x <= 0 || size.y <= 0 {
+            return Err(SnakeError::InvalidDimensions { cols: size.x, rows: size.y });
+        }
+        Ok(())
+    }
+
     pub fn get_size(&self) -> Point2D<i32> {
         self.size
     }
-    
-    fn pos_to_index(&self, pos: Point2D<i32>) -> Option<usize> {
-        if pos.x >= 0 && pos.x < self.size.x && pos.y >= 0 && pos.y < self.size.y {
-            Some((pos.x * self.size.x + pos.y) as usize)
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn contains(&self, pos: Point2D<i32>) -> bool {
+        pos.x >= 0 && pos.x < self.size.x && pos.y >= 0 && pos.y < self.size.y
+    }
+
+    pub fn pos_to_index(&self, pos: Point2D<i32>) -> Option<usize> {
+        if self.contains(pos) {
+            Some((pos.y * self.size.x + pos.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn index_to_pos(&self, index: usize) -> Option<Point2D<i32>> {
+        if index < self.data.len() {
+            let index = index as i32;
+            Some(Point2D::new(index % self.size.x, index / self.size.x))
         } else {
             None
         }
     }
+
     pub fn get(&self, index: usize) -> Option<&T> {
         self.data.get(index)
     }
@@ -46,4 +157,307 @@ impl<T: Default + Clone> Matrix2D<T> {
     pub fn get_pos_mut(&mut self, pos: Point2D<i32>) -> Option<&mut T> {
         self.pos_to_index(pos).and_then(|i| self.get_mut(i))
     }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.data.iter_mut()
+    }
+
+    pub fn iter_with_pos(&self) -> impl Iterator<Item = (Point2D<i32>, &T)> + '_ {
+        let cols = self.size.x;
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, item)| (Point2D::new(i as i32 % cols, i as i32 / cols), item))
+    }
+
+    pub fn iter_with_pos_mut(&mut self) -> impl Iterator<Item = (Point2D<i32>, &mut T)> + '_ {
+        let cols = self.size.x;
+        self.data
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, item)| (Point2D::new(i as i32 % cols, i as i32 / cols), item))
+    }
+
+    pub fn row(&self, y: i32) -> Option<&[T]> {
+        if y < 0 || y >= self.size.y {
+            return None;
+        }
+        let start = (y * self.size.x) as usize;
+        Some(&self.data[start..start + self.size.x as usize])
+    }
+
+    pub fn row_mut(&mut self, y: i32) -> Option<&mut [T]> {
+        if y < 0 || y >= self.size.y {
+            return None;
+        }
+        let start = (y * self.size.x) as usize;
+        Some(&mut self.data[start..start + self.size.x as usize])
+    }
+
+    pub fn rows(&self) -> std::slice::Chunks<'_, T> {
+        self.data.chunks(self.size.x as usize)
+    }
+
+    pub fn column(&self, x: i32) -> impl Iterator<Item = &T> + '_ {
+        let (skip, take) = if x >= 0 && x < self.size.x {
+            (x as usize, self.size.y as usize)
+        } else {
+            (0, 0)
+        };
+
+        self.data.iter().skip(skip).step_by(self.size.x as usize).take(take)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.size.x).map(move |x| self.column(x))
+    }
+
+    /// Up, right, down and left neighbors of `pos` that lie inside the matrix.
+    pub fn neighbors4(&self, pos: Point2D<i32>) -> impl Iterator<Item = Point2D<i32>> + '_ {
+        NEIGHBORS_4.iter().map(move |d| pos + *d).filter(move |p| self.contains(*p))
+    }
+
+    /// All eight surrounding cells of `pos` that lie inside the matrix, clockwise from the top.
+    pub fn neighbors8(&self, pos: Point2D<i32>) -> impl Iterator<Item = Point2D<i32>> + '_ {
+        NEIGHBORS_8.iter().map(move |d| pos + *d).filter(move |p| self.contains(*p))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Matrix2D<U>
+    where F: FnMut(&T) -> U {
+        Matrix2D {
+            size: self.size,
+            data: self.data.iter().map(f).collect(),
+        }
+    }
+
+    /// Read-only view of the `size` sized region starting at `origin`.
+    /// The whole region must lie inside the matrix.
+    pub fn view(&self, origin: Point2D<i32>, size: Point2D<i32>) -> Result<Matrix2DView<'_, T>, SnakeError> {
+        Self::check_size(&size)?;
+
+        for corner in [origin, origin + size - Point2D::new(1, 1)] {
+            if !self.contains(corner) {
+                return Err(SnakeError::OutOfBounds { pos: corner, size: self.size });
+            }
+        }
+
+        Ok(Matrix2DView { matrix: self, origin, size })
+    }
+}
+
+impl<T> Index<usize> for Matrix2D<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.data[index]
+    }
+}
+
+impl<T> IndexMut<usize> for Matrix2D<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.data[index]
+    }
+}
+
+impl<T> Index<Point2D<i32>> for Matrix2D<T> {
+    type Output = T;
+
+    fn index(&self, pos: Point2D<i32>) -> &T {
+        match self.get_pos(pos) {
+            Some(item) => item,
+            None => panic!("Position {:?} out of {}x{} matrix", pos, self.size.x, self.size.y),
+        }
+    }
+}
+
+impl<T> IndexMut<Point2D<i32>> for Matrix2D<T> {
+    fn index_mut(&mut self, pos: Point2D<i32>) -> &mut T {
+        let size = self.size;
+        match self.get_pos_mut(pos) {
+            Some(item) => item,
+            None => panic!("Position {:?} out of {}x{} matrix", pos, size.x, size.y),
+        }
+    }
+}
+
+/// Rectangular sub-region of a `Matrix2D`, addressed relative to its own origin.
+#[derive(Debug)]
+pub struct Matrix2DView<'a, T> {
+    matrix: &'a Matrix2D<T>,
+    origin: Point2D<i32>,
+    size: Point2D<i32>,
+}
+
+impl<T> Clone for Matrix2DView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Matrix2DView<'_, T> {}
+
+impl<'a, T> Matrix2DView<'a, T> {
+    pub fn get_size(&self) -> Point2D<i32> {
+        self.size
+    }
+
+    pub fn origin(&self) -> Point2D<i32> {
+        self.origin
+    }
+
+    pub fn contains(&self, pos: Point2D<i32>) -> bool {
+        pos.x >= 0 && pos.x < self.size.x && pos.y >= 0 && pos.y < self.size.y
+    }
+
+    pub fn get_pos(&self, pos: Point2D<i32>) -> Option<&'a T> {
+        if self.contains(pos) {
+            self.matrix.get_pos(self.origin + pos)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: i32) -> Option<&'a [T]> {
+        if y < 0 || y >= self.size.y {
+            return None;
+        }
+        let row = self.matrix.row(self.origin.y + y)?;
+        let start = self.origin.x as usize;
+        Some(&row[start..start + self.size.x as usize])
+    }
+
+    pub fn iter_with_pos(&self) -> impl Iterator<Item = (Point2D<i32>, &'a T)> + 'a {
+        let view = *self;
+        (0..self.size.y).flat_map(move |y| {
+            view.row(y)
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+                .map(move |(x, item)| (Point2D::new(x as i32, y), item))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 5 columns by 3 rows, every cell holding its own row-major index.
+    fn matrix() -> Matrix2D<i32> {
+        Matrix2D::from_fn(&Point2D::new(5, 3), |pos| pos.y * 5 + pos.x).unwrap()
+    }
+
+    #[test]
+    fn index_and_position_round_trip() {
+        let m = matrix();
+
+        for index in 0..m.len() {
+            let pos = m.index_to_pos(index).unwrap();
+            assert_eq!(m.pos_to_index(pos), Some(index));
+        }
+
+        assert_eq!(m.pos_to_index(Point2D::new(4, 0)), Some(4));
+        assert_eq!(m.pos_to_index(Point2D::new(0, 1)), Some(5));
+        assert_eq!(m.pos_to_index(Point2D::new(4, 2)), Some(14));
+        assert_eq!(m.index_to_pos(14), Some(Point2D::new(4, 2)));
+        assert_eq!(m.pos_to_index(Point2D::new(2, 4)), None);
+        assert_eq!(m.pos_to_index(Point2D::new(5, 0)), None);
+        assert_eq!(m.index_to_pos(15), None);
+    }
+
+    #[test]
+    fn index_by_position() {
+        let mut m = matrix();
+
+        for (pos, value) in m.iter_with_pos() {
+            assert_eq!(m[pos], *value);
+        }
+
+        assert_eq!(m[Point2D::new(3, 2)], 13);
+        m[Point2D::new(3, 2)] = -1;
+        assert_eq!(m[13], -1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_by_position_outside_panics() {
+        let _ = matrix()[Point2D::new(1, 3)];
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let m = matrix();
+
+        let rows: Vec<&[i32]> = m.rows().collect();
+        assert_eq!(rows, [&[0, 1, 2, 3, 4][..], &[5, 6, 7, 8, 9], &[10, 11, 12, 13, 14]]);
+        assert_eq!(m.row(1), Some(&[5, 6, 7, 8, 9][..]));
+        assert_eq!(m.row(3), None);
+
+        let columns: Vec<Vec<i32>> = m.columns().map(|column| column.copied().collect()).collect();
+        assert_eq!(columns.len(), 5);
+        assert_eq!(columns[0], [0, 5, 10]);
+        assert_eq!(columns[4], [4, 9, 14]);
+        assert_eq!(m.column(5).count(), 0);
+    }
+
+    #[test]
+    fn neighbors_at_corners() {
+        let m = matrix();
+        let neighbors4 = |x, y| m.neighbors4(Point2D::new(x, y)).collect::<Vec<_>>();
+        let neighbors8 = |x, y| m.neighbors8(Point2D::new(x, y)).collect::<Vec<_>>();
+
+        assert_eq!(neighbors4(0, 0), [Point2D::new(1, 0), Point2D::new(0, 1)]);
+        assert_eq!(neighbors4(4, 2), [Point2D::new(4, 1), Point2D::new(3, 2)]);
+        assert_eq!(neighbors4(2, 1).len(), 4);
+
+        assert_eq!(neighbors8(4, 0), [Point2D::new(4, 1), Point2D::new(3, 1), Point2D::new(3, 0)]);
+        assert_eq!(neighbors8(0, 2), [Point2D::new(0, 1), Point2D::new(1, 1), Point2D::new(1, 2)]);
+        assert_eq!(neighbors8(2, 1).len(), 8);
+    }
+
+    #[test]
+    fn resize_keeps_the_overlap() {
+        let mut m = matrix();
+        m.resize(&Point2D::new(3, 5), -1).unwrap();
+
+        assert_eq!(m.get_size(), Point2D::new(3, 5));
+        assert_eq!(m.len(), 15);
+        assert_eq!(m.row(0), Some(&[0, 1, 2][..]));
+        assert_eq!(m.row(2), Some(&[10, 11, 12][..]));
+        assert_eq!(m.row(3), Some(&[-1, -1, -1][..]));
+        assert_eq!(m[Point2D::new(2, 1)], 7);
+    }
+
+    #[test]
+    fn view_of_a_region() {
+        let m = matrix();
+        let view = m.view(Point2D::new(1, 1), Point2D::new(4, 2)).unwrap();
+
+        assert_eq!(view.get_pos(Point2D::new(0, 0)), Some(&6));
+        assert_eq!(view.get_pos(Point2D::new(3, 1)), Some(&14));
+        assert_eq!(view.get_pos(Point2D::new(4, 0)), None);
+        assert_eq!(view.row(1), Some(&[11, 12, 13, 14][..]));
+        assert_eq!(view.iter_with_pos().count(), 8);
+
+        assert!(matches!(
+            m.view(Point2D::new(2, 1), Point2D::new(4, 2)),
+            Err(SnakeError::OutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn deserialize_checks_the_cell_count() {
+        let m: Matrix2D<i32> = serde_json::from_str(&serde_json::to_string(&matrix()).unwrap()).unwrap();
+        assert_eq!(m[Point2D::new(4, 2)], 14);
+
+        let short = r#"{"size":{"x":5,"y":3},"data":[0,1,2]}"#;
+        assert!(serde_json::from_str::<Matrix2D<i32>>(short).is_err());
+
+        let empty = r#"{"size":{"x":0,"y":3},"data":[]}"#;
+        assert!(serde_json::from_str::<Matrix2D<i32>>(empty).is_err());
+    }
 }
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::level::{Level, LevelCell};
+use crate::matrix_2d::Matrix2D;
+use crate::point_2d::Point2D;
+use crate::rng::GameRng;
+use crate::BoardTopology;
+
+/// Scatters short wall segments over a level while keeping every free cell reachable
+/// from the spawn point.
+#[derive(Debug, Copy, Clone)]
+pub struct ObstacleGenerator {
+    pub density: f64,               // share of the free cells to turn into walls, 0.0 - 1.0
+    pub max_segment_length: usize,  // walls are placed as straight segments of 1 to this many cells
+    pub spawn_clearance: usize,     // free cells kept in front of the spawn point
+    pub seed: u64,
+}
+
+impl ObstacleGenerator {
+    pub fn new(density: f64, seed: u64) -> ObstacleGenerator {
+        ObstacleGenerator {
+            density,
+            max_segment_length: 4,
+            spawn_clearance: 3,
+            seed,
+        }
+    }
+
+    /// Copy of `level` with obstacles added to its free cells.
+    /// The density is a target, on crowded levels fewer walls may fit.
+    pub fn generate(&self, level: &Level) -> Level {
+        let mut rng = GameRng::new(self.seed);
+        let mut cells = level.cells.clone();
+        let size = cells.get_size();
+
+        // the spawn and the cells the snake heads into first stay free
+        let mut reserved = vec![level.spawn];
+        let mut pos = level.spawn;
+        for _ in 0..self.spawn_clearance {
+            pos = match level.topology.wrap(pos + level.direction.offset(), size) {
+                Some(next) => next,
+                None => break,
+            };
+            reserved.push(pos);
+        }
+
+        let free_cells = cells.iter().filter(|cell| **cell == LevelCell::Empty).count();
+        let target = (free_cells as f64 * self.density.clamp(0.0, 1.0)) as usize;
+        let max_attempts = target * 10 + 100;
+        let directions = [Point2D::new(1, 0), Point2D::new(0, 1)];
+
+        let mut placed = 0usize;
+        for _ in 0..max_attempts {
+            if placed >= target {
+                break;
+            }
+
+            let start = Point2D::new(rng.gen_range(0..size.x), rng.gen_range(0..size.y));
+            let step = directions[rng.gen_range(0..directions.len())];
+            let length = rng.gen_range(1..=self.max_segment_length.max(1)).min(target - placed);
+
+            let segment: Vec<Point2D<i32>> = (0..length as i32)
+                .map(|i| start + step * Point2D::new(i, i))
+                .take_while(|pos| cells.get_pos(*pos) == Some(&LevelCell::Empty) && !reserved.contains(pos))
+                .collect();
+
+            if segment.is_empty() {
+                continue;
+            }
+
+            for pos in &segment {
+                cells[*pos] = LevelCell::Wall;
+            }
+
+            if is_connected(&cells, level.spawn, level.topology) {
+                placed += segment.len();
+            } else {
+                for pos in &segment {
+                    cells[*pos] = LevelCell::Empty;
+                }
+            }
+        }
+
+        Level {
+            cells,
+            ..level.clone()
+        }
+    }
+}
+
+/// True when every non-wall cell can be reached from `start`.
+fn is_connected(cells: &Matrix2D<LevelCell>, start: Point2D<i32>, topology: BoardTopology) -> bool {
+    let size = cells.get_size();
+    let mut visited = vec![false; cells.len()];
+    let mut queue = VecDeque::from([start]);
+    let mut reached = 0usize;
+
+    if let Some(index) = cells.pos_to_index(start) {
+        visited[index] = true;
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        reached += 1;
+
+        for offset in [Point2D::new(1, 0), Point2D::new(-1, 0), Point2D::new(0, 1), Point2D::new(0, -1)] {
+            let next = match topology.wrap(pos + offset, size) {
+                Some(next) => next,
+                None => continue,
+            };
+            let index = match cells.pos_to_index(next) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            if !visited[index] && cells[index] != LevelCell::Wall {
+                visited[index] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    reached == cells.iter().filter(|cell| **cell != LevelCell::Wall).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    /// Cells reachable from the spawn, found by growing the set until it stops changing.
+    fn reachable(level: &Level) -> Vec<bool> {
+        let size = level.get_size();
+        let mut reached: Vec<bool> = level.cells.iter_with_pos().map(|(pos, _)| pos == level.spawn).collect();
+
+        loop {
+            let mut changed = false;
+            for (pos, _) in level.cells.iter_with_pos().filter(|(_, cell)| **cell != LevelCell::Wall) {
+                let index = level.cells.pos_to_index(pos).unwrap();
+                let next_to_reached = [Direction::Left, Direction::Right, Direction::Up, Direction::Down]
+                    .iter()
+                    .filter_map(|direction| level.topology.wrap(pos + direction.offset(), size))
+                    .any(|next| reached[level.cells.pos_to_index(next).unwrap()]);
+
+                if !reached[index] && next_to_reached {
+                    reached[index] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return reached;
+            }
+        }
+    }
+
+    #[test]
+    fn generated_levels_stay_playable() {
+        for topology in [BoardTopology::Walled, BoardTopology::Wrap] {
+            let base = Level::open(16, 12, topology).unwrap();
+            let free_cells = base.cells.iter().filter(|cell| **cell == LevelCell::Empty).count();
+
+            for density in [0.1, 0.3, 0.6] {
+                for seed in 0..8 {
+                    let generator = ObstacleGenerator::new(density, seed);
+                    let level = generator.generate(&base);
+                    let walls = level.cells.iter().filter(|cell| **cell == LevelCell::Wall).count()
+                        - base.cells.iter().filter(|cell| **cell == LevelCell::Wall).count();
+
+                    assert!(walls > 0 && walls <= (free_cells as f64 * density) as usize);
+                    let reached = reachable(&level);
+                    for (cell, reached) in level.cells.iter().zip(reached) {
+                        assert!(reached || *cell == LevelCell::Wall, "{:?} {} {}", topology, density, seed);
+                    }
+
+                    let mut pos = level.spawn;
+                    for _ in 0..=generator.spawn_clearance {
+                        assert_eq!(level.cells.get_pos(pos), Some(&LevelCell::Empty));
+                        pos = topology.wrap(pos + level.direction.offset(), level.get_size()).unwrap();
+                    }
+
+                    assert!(generator.generate(&base).cells.iter().eq(level.cells.iter()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn clearance_wraps_around_the_field_edge() {
+        let mut base = Level::open(8, 8, BoardTopology::Wrap).unwrap();
+        base.spawn = Point2D::new(6, 3);
+        base.direction = Direction::Right;
+
+        let level = ObstacleGenerator::new(0.9, 3).generate(&base);
+        for x in [6, 7, 0, 1] {
+            assert_eq!(level.cells.get_pos(Point2D::new(x, 3)), Some(&LevelCell::Empty));
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use std::ops::{Add, Sub, Div, Mul};
 use std::cmp::PartialEq;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Point2D<T> {
     pub x: T,
     pub y: T,
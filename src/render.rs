@@ -0,0 +1,39 @@
+use graphics::math::Scalar;
+use graphics::rectangle;
+use graphics::types::Rectangle;
+use piston::Size;
+use crate::point_2d::Point2D;
+
+pub struct RenderSettings {
+    pub viewport_size: Point2D<usize>,
+    pub grid_size: Point2D<usize>,
+    pub square_size: Point2D<Scalar>,
+    pub square: Rectangle,
+}
+
+impl RenderSettings {
+    pub fn new(viewport_size: [usize; 2], grid_size: [usize; 2]) -> RenderSettings {
+        let viewport_size = Point2D::new_from_array(viewport_size);
+        let grid_size = Point2D::new_from_array(grid_size);
+        let square_size = Point2D {
+            x: viewport_size.x as Scalar / grid_size.x as Scalar,
+            y: viewport_size.y as Scalar / grid_size.y as Scalar,
+        };
+
+        RenderSettings {
+            viewport_size,
+            grid_size,
+            square_size,
+            square: rectangle::rectangle_by_corners(
+                0.0,
+                0.0,
+                square_size.x - 1.,
+                square_size.y - 1.,
+            )
+        }
+    }
+
+    pub fn get_viewport_size(&self) -> Size {
+        Size { width: self.viewport_size.x as f64, height: self.viewport_size.y as f64 }
+    }
+}
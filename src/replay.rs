@@ -0,0 +1,383 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::SnakeError;
+use crate::{BoardTopology, Direction, Game, GameOverType, GameState, StepOutcome};
+
+pub const REPLAY_VERSION: u32 = 1;
+
+const REPLAY_MAGIC: &str = "my-snake-replay";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplayEventKind {
+    Play,   // Game::play, starts or resumes the round
+    Turn,   // Game::set_movement_direction
+}
+
+/// Input that arrived after `step` steps of the round were played.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReplayEvent {
+    pub step: usize,
+    pub kind: ReplayEventKind,
+    pub direction: Direction,
+}
+
+/// Everything needed to play a round again: the board, the round seed and the inputs.
+/// Food placement, score rules and speed curve are the `Game` defaults.
+///
+/// Text format, one item per line:
+/// ```text
+/// my-snake-replay 1
+/// seed 1234
+/// board 12 12 walled
+/// steps 57
+/// 0 PR
+/// 3 TU
+/// ```
+/// Each event line is `<step> <P|T><L|R|U|D>`, `P` for play and `T` for a turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub cols: usize,
+    pub rows: usize,
+    pub topology: BoardTopology,
+    pub steps: usize,   // steps played in the recorded round, including a fatal one
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn new_game(&self) -> Result<Game, SnakeError> {
+        Game::new(self.cols, self.rows, self.topology, self.seed)
+    }
+}
+
+fn direction_code(direction: Direction) -> char {
+    match direction {
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+    }
+}
+
+pub(crate) fn direction_from_code(code: char) -> Option<Direction> {
+    match code {
+        'L' => Some(Direction::Left),
+        'R' => Some(Direction::Right),
+        'U' => Some(Direction::Up),
+        'D' => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let topology = match self.topology {
+            BoardTopology::Walled => "walled",
+            BoardTopology::Wrap => "wrap",
+        };
+
+        writeln!(f, "{} {}", REPLAY_MAGIC, self.version)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "board {} {} {}", self.cols, self.rows, topology)?;
+        writeln!(f, "steps {}", self.steps)?;
+
+        for event in &self.events {
+            let kind = match event.kind {
+                ReplayEventKind::Play => 'P',
+                ReplayEventKind::Turn => 'T',
+            };
+            writeln!(f, "{} {}{}", event.step, kind, direction_code(event.direction))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        let mut next_line = |name: &str| {
+            lines.next().ok_or_else(|| SnakeError::ReplayParse {
+                line: 0,
+                message: format!("missing {} line", name),
+            })
+        };
+
+        let error = |line: usize, message: &str| SnakeError::ReplayParse { line, message: message.to_string() };
+
+        let (line, header) = next_line("header")?;
+        let version = match header.split_once(' ') {
+            Some((REPLAY_MAGIC, version)) => version.parse::<u32>().map_err(|_| error(line, "invalid version"))?,
+            _ => return Err(error(line, "not a replay file")),
+        };
+        if version != REPLAY_VERSION {
+            return Err(error(line, &format!("unsupported version {}", version)));
+        }
+
+        let (line, seed) = next_line("seed")?;
+        let seed = seed.strip_prefix("seed ")
+            .and_then(|seed| seed.parse::<u64>().ok())
+            .ok_or_else(|| error(line, "expected `seed <number>`"))?;
+
+        let (line, board) = next_line("board")?;
+        let board: Vec<&str> = board.split_whitespace().collect();
+        let (cols, rows, topology) = match board.as_slice() {
+            ["board", cols, rows, topology] => (
+                cols.parse::<usize>().map_err(|_| error(line, "invalid column count"))?,
+                rows.parse::<usize>().map_err(|_| error(line, "invalid row count"))?,
+                match *topology {
+                    "walled" => BoardTopology::Walled,
+                    "wrap" => BoardTopology::Wrap,
+                    _ => return Err(error(line, "topology must be `walled` or `wrap`")),
+                },
+            ),
+            _ => return Err(error(line, "expected `board <cols> <rows> <walled|wrap>`")),
+        };
+
+        let (line, steps) = next_line("steps")?;
+        let steps = steps.strip_prefix("steps ")
+            .and_then(|steps| steps.parse::<usize>().ok())
+            .ok_or_else(|| error(line, "expected `steps <number>`"))?;
+
+        let mut events = Vec::new();
+        for (line, event) in lines.filter(|(_, line)| !line.is_empty()) {
+            let (step, code) = event.split_once(' ').ok_or_else(|| error(line, "expected `<step> <event>`"))?;
+            let step = step.parse::<usize>().map_err(|_| error(line, "invalid step"))?;
+
+            let mut code = code.chars();
+            let kind = match code.next() {
+                Some('P') => ReplayEventKind::Play,
+                Some('T') => ReplayEventKind::Turn,
+                _ => return Err(error(line, "event must start with P or T")),
+            };
+            let direction = match (code.next().and_then(direction_from_code), code.next()) {
+                (Some(direction), None) => direction,
+                _ => return Err(error(line, "direction must be one of L, R, U, D")),
+            };
+
+            events.push(ReplayEvent { step, kind, direction });
+        }
+
+        Ok(Replay { version, seed, cols, rows, topology, steps, events })
+    }
+}
+
+/// Forwards inputs to a game and remembers them for a replay of the current round.
+#[derive(Debug, Clone)]
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    /// Starts recording; call at the start of the round.
+    pub fn new(game: &Game) -> ReplayRecorder {
+        ReplayRecorder {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                seed: game.seed,
+                cols: game.field_size.x,
+                rows: game.field_size.y,
+                topology: game.topology,
+                steps: 0,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    fn record(&mut self, game: &Game, kind: ReplayEventKind, direction: Direction) {
+        if let GameState::GameOver(_) = game.state {
+            return;
+        }
+
+        self.replay.events.push(ReplayEvent { step: game.step_count, kind, direction });
+    }
+
+    pub fn play(&mut self, game: &mut Game, direction: Direction) {
+        self.record(game, ReplayEventKind::Play, direction);
+        game.play(direction);
+    }
+
+    pub fn set_movement_direction(&mut self, game: &mut Game, direction: Direction) {
+        self.record(game, ReplayEventKind::Turn, direction);
+        game.set_movement_direction(direction);
+    }
+
+    /// The replay of the round so far.
+    pub fn finish(&self, game: &Game) -> Replay {
+        // a collision ends the round without counting the step it happened in
+        let fatal_step = matches!(
+            game.state,
+            GameState::GameOver(GameOverType::BorderHit | GameOverType::SelfBite)
+        );
+
+        Replay {
+            steps: game.step_count + fatal_step as usize,
+            ..self.replay.clone()
+        }
+    }
+}
+
+/// Feeds the recorded inputs into a game created by `Replay::new_game`.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    next_event: usize,
+    steps_played: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer { replay, next_event: 0, steps_played: 0 }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Starts the playback over with a fresh game.
+    pub fn restart(&mut self) -> Result<Game, SnakeError> {
+        self.next_event = 0;
+        self.steps_played = 0;
+        self.replay.new_game()
+    }
+
+    pub fn is_finished(&self, game: &Game) -> bool {
+        matches!(game.state, GameState::GameOver(_)) || self.steps_played >= self.replay.steps
+    }
+
+    /// Applies the inputs recorded before the next step and plays it.
+    pub fn step(&mut self, game: &mut Game) -> StepOutcome {
+        if self.is_finished(game) {
+            return StepOutcome::Idle;
+        }
+
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            if event.step > game.step_count {
+                break;
+            }
+
+            match event.kind {
+                ReplayEventKind::Play => game.play(event.direction),
+                ReplayEventKind::Turn => game.set_movement_direction(event.direction),
+            }
+            self.next_event += 1;
+        }
+
+        let outcome = game.step(None);
+        if !matches!(outcome, StepOutcome::Idle) {
+            self.steps_played += 1;
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLAY: &str = "my-snake-replay 1\nseed 1234\nboard 12 10 wrap\nsteps 57\n0 PR\n3 TU\n\n10 TL\n";
+
+    fn parse_error(text: &str) -> (usize, String) {
+        match text.parse::<Replay>() {
+            Err(SnakeError::ReplayParse { line, message }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    /// Records a round driven by `turns` (step, direction), until it ends or `max_steps` are played.
+    fn record(seed: u64, turns: &[(usize, Direction)], max_steps: usize) -> (Game, Replay) {
+        let mut game = Game::new(12, 12, BoardTopology::Walled, seed).unwrap();
+        let mut recorder = ReplayRecorder::new(&game);
+        recorder.play(&mut game, Direction::Right);
+
+        for _ in 0..max_steps {
+            let step = game.step_count;
+            for (_, direction) in turns.iter().filter(|(turn_step, _)| *turn_step == step) {
+                recorder.set_movement_direction(&mut game, *direction);
+            }
+            if let StepOutcome::RoundOver(_) = game.step(None) {
+                break;
+            }
+        }
+
+        let replay = recorder.finish(&game);
+        (game, replay)
+    }
+
+    /// Plays `replay` through its text form, returns the final game and the steps played.
+    fn play_back(replay: &Replay) -> (Game, usize) {
+        let mut player = ReplayPlayer::new(replay.to_string().parse().unwrap());
+        let mut game = player.restart().unwrap();
+
+        let mut steps = 0;
+        while !player.is_finished(&game) {
+            assert!(!matches!(player.step(&mut game), StepOutcome::Idle));
+            steps += 1;
+        }
+        (game, steps)
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let replay: Replay = REPLAY.parse().unwrap();
+
+        assert_eq!((replay.seed, replay.cols, replay.rows, replay.topology), (1234, 12, 10, BoardTopology::Wrap));
+        assert_eq!(replay.steps, 57);
+        assert_eq!(replay.events, [
+            ReplayEvent { step: 0, kind: ReplayEventKind::Play, direction: Direction::Right },
+            ReplayEvent { step: 3, kind: ReplayEventKind::Turn, direction: Direction::Up },
+            ReplayEvent { step: 10, kind: ReplayEventKind::Turn, direction: Direction::Left },
+        ]);
+        assert_eq!(replay.to_string().parse::<Replay>().unwrap(), replay);
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        assert_eq!(parse_error("snake 1\n").0, 1);
+        assert_eq!(parse_error("my-snake-replay 1\nseed x\n").0, 2);
+        assert_eq!(parse_error("my-snake-replay 1\nseed 1\nboard 12 10 round\n").0, 3);
+        assert_eq!(parse_error("my-snake-replay 1\nseed 1\nboard 12 10\n").0, 3);
+        assert_eq!(parse_error("my-snake-replay 1\nseed 1\nboard 12 10 wrap\nsteps\n").0, 4);
+        assert_eq!(parse_error(&REPLAY.replace("3 TU", "3 XU")).0, 6);
+        assert_eq!(parse_error(&REPLAY.replace("10 TL", "10 TLL")).0, 8);
+        assert_eq!(parse_error(&REPLAY.replace("0 PR", "-1 PR")).0, 5);
+        assert_eq!(parse_error("my-snake-replay 1\nseed 1\n"), (0, "missing board line".to_string()));
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        assert_eq!(parse_error(&REPLAY.replace("replay 1", "replay 2")), (1, "unsupported version 2".to_string()));
+        assert_eq!(parse_error(&REPLAY.replace("replay 1", "replay one")).0, 1);
+    }
+
+    #[test]
+    fn playback_ends_like_the_recorded_round() {
+        // up into the wall, the fatal step is counted by the replay but not by the game
+        let (recorded, replay) = record(5, &[(2, Direction::Up), (4, Direction::Left)], 100);
+        assert!(matches!(recorded.state, GameState::GameOver(GameOverType::BorderHit)));
+        assert_eq!(replay.steps, recorded.step_count + 1);
+
+        let (played, steps) = play_back(&replay);
+        assert_eq!(steps, replay.steps);
+        assert_eq!(played.state, recorded.state);
+        assert_eq!(played.step_count, recorded.step_count);
+        assert_eq!(played.snake().head_index, recorded.snake().head_index);
+        assert_eq!(played.snake().body, recorded.snake().body);
+    }
+
+    #[test]
+    fn playback_of_an_unfinished_round_stops_at_its_last_step() {
+        let (recorded, replay) = record(8, &[(1, Direction::Down), (3, Direction::Left)], 6);
+        assert_eq!(recorded.state, GameState::Playing);
+        assert_eq!(replay.steps, 6);
+
+        let (played, steps) = play_back(&replay);
+        assert_eq!(steps, 6);
+        assert_eq!(played.state, GameState::Playing);
+        assert_eq!(played.snake().body, recorded.snake().body);
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SnakeError;
+use crate::food::FoodSpawner;
+use crate::level::{Level, LevelCell};
+use crate::matrix_2d::Matrix2D;
+use crate::point_2d::Point2D;
+use crate::rng::GameRng;
+use crate::score::{RoundStats, Score, ScoreRules};
+use crate::speed::SpeedCurve;
+use crate::{
+    BoardTopology, Cell, CellType, Direction, Game, GameOverType, GameState, Snake, SnakeBodyPart,
+};
+
+/// Version of the save schema written by this build.
+///
+/// When `Game` changes in a way old saves cannot be read as, bump the version and add
+/// a step to `migrate_json`/`migrate_binary` that upgrades the previous version's data.
+pub const SAVE_VERSION: u32 = 3;
+
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+fn format_error(e: impl std::fmt::Display) -> SnakeError {
+    SnakeError::SaveFormat { message: e.to_string() }
+}
+
+pub fn to_json(game: &Game) -> Result<String, SnakeError> {
+    serde_json::to_string(&SaveRef { version: SAVE_VERSION, game }).map_err(format_error)
+}
+
+pub fn from_json(text: &str) -> Result<Game, SnakeError> {
+    let mut save: Value = serde_json::from_str(text).map_err(format_error)?;
+
+    let version = save.get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| format_error("missing save version"))?;
+    let game = save.get_mut("game")
+        .map(Value::take)
+        .ok_or_else(|| format_error("missing game"))?;
+
+    check_loaded(migrate_json(version as u32, game)?)
+}
+
+fn migrate_json(version: u32, game: Value) -> Result<Game, SnakeError> {
+    match version {
+        1 => serde_json::from_value::<GameV1>(game).map(|game| game.upgrade().upgrade()).map_err(format_error),
+        2 => serde_json::from_value::<GameV2>(game).map(GameV2::upgrade).map_err(format_error),
+        SAVE_VERSION => serde_json::from_value(game).map_err(format_error),
+        _ => Err(SnakeError::UnsupportedSaveVersion { version, supported: SAVE_VERSION }),
+    }
+}
+
+/// Compact binary save: the schema version as a little endian `u32` followed by the game.
+pub fn to_binary(game: &Game) -> Result<Vec<u8>, SnakeError> {
+    bincode::serialize(&(SAVE_VERSION, game)).map_err(format_error)
+}
+
+pub fn from_binary(bytes: &[u8]) -> Result<Game, SnakeError> {
+    let version: u32 = bincode::deserialize(bytes).map_err(format_error)?;
+
+    check_loaded(migrate_binary(version, bytes)?)
+}
+
+fn migrate_binary(version: u32, bytes: &[u8]) -> Result<Game, SnakeError> {
+    match version {
+        1 => {
+            let (_, game): (u32, GameV1) = bincode::deserialize(bytes).map_err(format_error)?;
+            Ok(game.upgrade().upgrade())
+        },
+        2 => {
+            let (_, game): (u32, GameV2) = bincode::deserialize(bytes).map_err(format_error)?;
+            Ok(game.upgrade())
+        },
+        SAVE_VERSION => {
+            let (_, game): (u32, Game) = bincode::deserialize(bytes).map_err(format_error)?;
+            Ok(game)
+        },
+        _ => Err(SnakeError::UnsupportedSaveVersion { version, supported: SAVE_VERSION }),
+    }
+}
+
+/// Rejects saves that deserialize fine but would make the game index out of its field.
+fn check_loaded(game: Game) -> Result<Game, SnakeError> {
+    let cells = game.field_size.x * game.field_size.y;
+    let size = game.field.get_size();
+
+    if size.x as usize != game.field_size.x
+        || size.y as usize != game.field_size.y
+        || game.field.len() != cells {
+        return Err(format_error("field does not match the field size"));
+    }
+
+    // rounds are rebuilt from the level, it has to cover the field exactly
+    if let Some(level) = &game.level {
+        let spawn = level.cells.get_pos(level.spawn);
+
+        if level.cells.get_size() != size
+            || level.cells.len() != game.field.len()
+            || spawn.is_none()
+            || spawn == Some(&LevelCell::Wall) {
+            return Err(format_error("level does not match the field"));
+        }
+    }
+
+    if game.snakes.is_empty() {
+        return Err(format_error("no snakes"));
+    }
+
+    // a dead snake may have left the field already, so only living ones need a head at the front
+    for snake in &game.snakes {
+        if (snake.is_alive() && snake.body.front() != Some(&snake.head_index))
+            || snake.head_index >= cells
+            || snake.body.iter().any(|index| *index >= cells) {
+            return Err(format_error("snake does not fit the field"));
+        }
+    }
+
+    Ok(game)
+}
+
+/// `CellType` of version 2 saves and older, snakes had no owner.
+#[derive(Deserialize)]
+enum CellTypeV2 {
+    Uninitialized,
+    Empty,
+    Border,
+    Snake(SnakeBodyPart),
+    Food,
+}
+
+impl CellTypeV2 {
+    fn upgrade(&self) -> CellType {
+        match *self {
+            CellTypeV2::Uninitialized => CellType::Uninitialized,
+            CellTypeV2::Empty => CellType::Empty,
+            CellTypeV2::Border => CellType::Border,
+            CellTypeV2::Snake(part) => CellType::Snake(0, part),
+            CellTypeV2::Food => CellType::Food,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CellV2 {
+    pos: Point2D<i32>,
+    cell_type: CellTypeV2,
+    rendered_cell_type: CellTypeV2,
+}
+
+/// `Game` of version 1 saves, with a single snake and no level. The field order matters for bincode.
+#[derive(Deserialize)]
+struct GameV1 {
+    state: GameState,
+    field_size: Point2D<usize>,
+    topology: BoardTopology,
+    field: Matrix2D<CellV2>,
+    snake_head_index: usize,
+    snake_body: VecDeque<usize>,
+    direction: Direction,
+    input_queue: VecDeque<Direction>,
+    snake_length: usize,
+    step_count: usize,
+    food_spawner: FoodSpawner,
+    score_rules: ScoreRules,
+    score: Score,
+    stats: RoundStats,
+    speed_curve: SpeedCurve,
+    round: usize,
+    seed: u64,
+    rng: GameRng,
+}
+
+impl GameV1 {
+    fn upgrade(self) -> GameV2 {
+        GameV2 {
+            state: self.state,
+            field_size: self.field_size,
+            topology: self.topology,
+            level: None,
+            field: self.field,
+            snake_head_index: self.snake_head_index,
+            snake_body: self.snake_body,
+            direction: self.direction,
+            input_queue: self.input_queue,
+            snake_length: self.snake_length,
+            step_count: self.step_count,
+            food_spawner: self.food_spawner,
+            score_rules: self.score_rules,
+            score: self.score,
+            stats: self.stats,
+            speed_curve: self.speed_curve,
+            round: self.round,
+            seed: self.seed,
+            rng: self.rng,
+        }
+    }
+}
+
+/// `Game` of version 2 saves, with a single snake. The field order matters for bincode.
+#[derive(Deserialize)]
+struct GameV2 {
+    state: GameState,
+    field_size: Point2D<usize>,
+    topology: BoardTopology,
+    level: Option<Level>,
+    field: Matrix2D<CellV2>,
+    snake_head_index: usize,
+    snake_body: VecDeque<usize>,
+    direction: Direction,
+    input_queue: VecDeque<Direction>,
+    #[allow(dead_code)]
+    snake_length: usize,
+    step_count: usize,
+    food_spawner: FoodSpawner,
+    score_rules: ScoreRules,
+    score: Score,
+    stats: RoundStats,
+    speed_curve: SpeedCurve,
+    round: usize,
+    seed: u64,
+    rng: GameRng,
+}
+
+impl GameV2 {
+    fn upgrade(self) -> Game {
+        let death = match self.state {
+            GameState::GameOver(GameOverType::PlaygroundFilled) => None,
+            GameState::GameOver(game_over_type) => Some(game_over_type),
+            _ => None,
+        };
+
+        let snake = Snake {
+            head_index: self.snake_head_index,
+            body: self.snake_body,
+            direction: self.direction,
+            input_queue: self.input_queue,
+            score: self.score,
+            death,
+        };
+
+        Game {
+            state: self.state,
+            field_size: self.field_size,
+            topology: self.topology,
+            level: self.level,
+            field: self.field.map(|cell| Cell {
+                pos: cell.pos,
+                cell_type: cell.cell_type.upgrade(),
+                rendered_cell_type: cell.rendered_cell_type.upgrade(),
+            }),
+            snakes: vec![snake],
+            step_count: self.step_count,
+            food_spawner: self.food_spawner,
+            score_rules: self.score_rules,
+            stats: self.stats,
+            speed_curve: self.speed_curve,
+            round: self.round,
+            seed: self.seed,
+            rng: self.rng,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    fn game_with_level(level: Level) -> Game {
+        let mut game = Game::new(12, 12, BoardTopology::Walled, 1).unwrap();
+        game.level = Some(level);
+        game
+    }
+
+    /// 6x5 board after eating the food right of the start and turning down, written by version 1.
+    const SAVE_V1_JSON: &str = include_str!("../fixtures/save_v1.json");
+    const SAVE_V1_BIN: &[u8] = include_bytes!("../fixtures/save_v1.bin");
+    /// 7x6 level with a pillar above the spawn, the same moves heading left, written by version 2.
+    const SAVE_V2_JSON: &str = include_str!("../fixtures/save_v2.json");
+    const SAVE_V2_BIN: &[u8] = include_bytes!("../fixtures/save_v2.bin");
+
+    fn assert_after_meal_and_turn(game: &Game, head: (i32, i32)) {
+        assert_eq!(game.snakes.len(), 1);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.step_count, 2);
+
+        let snake = game.snake();
+        assert_eq!(game.get_point_from_index(snake.head_index), Point2D::new(head.0, head.1));
+        assert_eq!(snake.len(), 2);
+        assert_eq!(snake.direction, Direction::Down);
+        assert!(snake.is_alive());
+        assert_eq!(game.field[snake.head_index].cell_type, CellType::Snake(0, SnakeBodyPart::Head(1)));
+    }
+
+    #[test]
+    fn migrates_version_1_saves() {
+        for game in [from_json(SAVE_V1_JSON).unwrap(), from_binary(SAVE_V1_BIN).unwrap()] {
+            assert_after_meal_and_turn(&game, (4, 3));
+            assert_eq!(game.field_size, Point2D::new(6, 5));
+            assert!(game.level.is_none());
+        }
+    }
+
+    #[test]
+    fn migrates_version_2_saves() {
+        for mut game in [from_json(SAVE_V2_JSON).unwrap(), from_binary(SAVE_V2_BIN).unwrap()] {
+            assert_after_meal_and_turn(&game, (2, 4));
+            assert_eq!(game.level.as_ref().map(|level| level.name.as_str()), Some("Pillar"));
+
+            game.new_round();
+            assert_eq!(game.field[game.get_field_index(3, 2)].cell_type, CellType::Border);
+            assert_eq!(game.snake().head_index, game.get_field_index(3, 3));
+        }
+    }
+
+    #[test]
+    fn round_trips_the_current_version() {
+        let mut game = from_json(SAVE_V2_JSON).unwrap();
+        game.step(Some(Direction::Right));
+
+        for loaded in [from_json(&to_json(&game).unwrap()).unwrap(), from_binary(&to_binary(&game).unwrap()).unwrap()] {
+            assert_eq!(loaded.snake().body, game.snake().body);
+            assert_eq!(loaded.step_count, game.step_count);
+            assert_eq!(loaded.rng.state(), game.rng.state());
+        }
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let text = SAVE_V1_JSON.replacen("\"version\":1", "\"version\":99", 1);
+        let mut bytes = SAVE_V1_BIN.to_vec();
+        bytes[0] = 99;
+
+        for result in [from_json(&text), from_binary(&bytes)] {
+            assert!(matches!(result, Err(SnakeError::UnsupportedSaveVersion { version: 99, supported: SAVE_VERSION })));
+        }
+    }
+
+    #[test]
+    fn rejects_level_of_another_size() {
+        let game = game_with_level(Level::open(6, 6, BoardTopology::Walled).unwrap());
+
+        assert!(matches!(from_json(&to_json(&game).unwrap()), Err(SnakeError::SaveFormat { .. })));
+        assert!(matches!(from_binary(&to_binary(&game).unwrap()), Err(SnakeError::SaveFormat { .. })));
+    }
+
+    #[test]
+    fn rejects_level_spawning_off_the_field_or_in_a_wall() {
+        for spawn in [Point2D::new(12, 3), Point2D::new(-1, 3), Point2D::new(0, 0)] {
+            let mut level = Level::open(12, 12, BoardTopology::Walled).unwrap();
+            level.spawn = spawn;
+            let game = game_with_level(level);
+
+            assert!(matches!(from_json(&to_json(&game).unwrap()), Err(SnakeError::SaveFormat { .. })));
+        }
+    }
+
+    #[test]
+    fn loads_level_game() {
+        let mut game = Game::from_level(Level::open(12, 8, BoardTopology::Walled).unwrap(), 1).unwrap();
+        game.play(Direction::Up);
+        game.step(None);
+
+        let mut loaded = from_binary(&to_binary(&game).unwrap()).unwrap();
+        assert_eq!(loaded.snake().head_index, game.snake().head_index);
+
+        loaded.new_round();
+        assert_eq!(loaded.field.len(), 12 * 8);
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use crate::GameOverType;
+
+/// Configurable scoring. All bonuses only ever add points, there are no penalties.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct ScoreRules {
+    pub points_per_food: u64,
+    pub speed_bonus: u64,           // extra points for food eaten quickly ...
+    pub speed_bonus_steps: usize,   // ... within this many steps since the previous meal
+    pub streak_steps: usize,        // meals at most this many steps apart build up a streak
+    pub max_multiplier: u64,        // each streak level adds 1 to the multiplier, up to this value
+}
+
+impl Default for ScoreRules {
+    fn default() -> Self {
+        ScoreRules {
+            points_per_food: 10,
+            speed_bonus: 5,
+            speed_bonus_steps: 10,
+            streak_steps: 20,
+            max_multiplier: 5,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Score {
+    pub points: u64,
+    pub multiplier: u64,
+    pub streak: usize,
+    #[serde(with = "meal_step")]
+    pub last_meal_step: Option<usize>,  // None until the first meal of the round
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Score {
+            points: 0,
+            multiplier: 1,
+            streak: 0,
+            last_meal_step: None,
+        }
+    }
+}
+
+impl Score {
+    /// Accounts a meal eaten at `step` and returns the points it was worth.
+    /// The first meal of a round neither continues a streak nor earns the speed bonus.
+    pub fn record_meal(&mut self, rules: &ScoreRules, step: usize) -> u64 {
+        let steps_since_last_meal = self.last_meal_step.map(|last| step - last);
+        let within = |steps: usize| steps_since_last_meal.is_some_and(|since| since <= steps);
+
+        if within(rules.streak_steps) {
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+        }
+
+        self.multiplier = (1 + self.streak as u64).min(rules.max_multiplier.max(1));
+        self.last_meal_step = Some(step);
+
+        let mut points = rules.points_per_food * self.multiplier;
+        if within(rules.speed_bonus_steps) {
+            points += rules.speed_bonus;
+        }
+
+        self.points += points;
+        points
+    }
+}
+
+/// Saves store the step of the last meal as a plain number. Meals are counted after the step
+/// that leads to them, so no meal happens at step 0 and it stands for "no meal yet".
+mod meal_step {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(step: &Option<usize>, serializer: S) -> Result<S::Ok, S::Error> {
+        step.unwrap_or(0).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<usize>, D::Error> {
+        usize::deserialize(deserializer).map(|step| Some(step).filter(|step| *step > 0))
+    }
+}
+
+/// Statistics of a single round, complete once the round is over.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct RoundStats {
+    pub score: u64,
+    pub food_eaten: usize,
+    pub steps: usize,
+    pub max_length: usize,
+    pub time_played: f64,   // seconds spent in the Playing state
+    pub game_over: Option<GameOverType>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records meals at `steps` and returns the points of each.
+    fn meals(rules: &ScoreRules, steps: &[usize]) -> (Score, Vec<u64>) {
+        let mut score = Score::default();
+        let points = steps.iter().map(|step| score.record_meal(rules, *step)).collect();
+        (score, points)
+    }
+
+    #[test]
+    fn first_meal_is_worth_the_base_points() {
+        let (score, points) = meals(&ScoreRules::default(), &[3]);
+
+        assert_eq!(points, [10]);
+        assert_eq!((score.streak, score.multiplier, score.last_meal_step), (0, 1, Some(3)));
+    }
+
+    #[test]
+    fn streak_builds_up_and_resets() {
+        let rules = ScoreRules { speed_bonus: 0, ..ScoreRules::default() };
+
+        // 20 steps apart still continue the streak, 21 break it
+        let (score, points) = meals(&rules, &[5, 25, 45, 66, 80]);
+
+        assert_eq!(points, [10, 20, 30, 10, 20]);
+        assert_eq!((score.streak, score.multiplier, score.points), (1, 2, 90));
+    }
+
+    #[test]
+    fn multiplier_is_capped() {
+        let rules = ScoreRules { speed_bonus: 0, max_multiplier: 3, ..ScoreRules::default() };
+        let (score, points) = meals(&rules, &[1, 2, 3, 4, 5]);
+
+        assert_eq!(points, [10, 20, 30, 30, 30]);
+        assert_eq!((score.streak, score.multiplier), (4, 3));
+    }
+
+    #[test]
+    fn quick_meals_earn_the_speed_bonus() {
+        let rules = ScoreRules { max_multiplier: 1, ..ScoreRules::default() };
+
+        // the bonus needs a previous meal at most 10 steps before
+        let (_, points) = meals(&rules, &[4, 14, 25]);
+
+        assert_eq!(points, [10, 15, 10]);
+    }
+
+    #[test]
+    fn saves_store_no_meal_as_step_0() {
+        let json = serde_json::to_string(&Score::default()).unwrap();
+        assert!(json.contains("\"last_meal_step\":0"));
+        assert_eq!(serde_json::from_str::<Score>(&json).unwrap().last_meal_step, None);
+
+        let mut score = Score::default();
+        score.record_meal(&ScoreRules::default(), 7);
+        let bytes = bincode::serialize(&score).unwrap();
+        assert_eq!(bincode::deserialize::<Score>(&bytes).unwrap().last_meal_step, Some(7));
+    }
+}
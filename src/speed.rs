@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// How the time between two game steps, in seconds, shrinks as the snake eats.
+/// `Linear` and `Stepped` never go below their `min` interval.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum SpeedCurve {
+    Constant {
+        interval: f64,
+    },
+    /// Gets faster by `decrease_per_food` seconds with every meal.
+    Linear {
+        initial: f64,
+        decrease_per_food: f64,
+        min: f64,
+    },
+    /// Multiplies the interval by `factor` after every `foods_per_step` meals.
+    Stepped {
+        initial: f64,
+        foods_per_step: usize,
+        factor: f64,
+        min: f64,
+    },
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        SpeedCurve::Linear {
+            initial: 0.5,
+            decrease_per_food: 0.01,
+            min: 0.1,
+        }
+    }
+}
+
+impl SpeedCurve {
+    /// Step interval in seconds after `food_eaten` meals.
+    pub fn interval(&self, food_eaten: usize) -> f64 {
+        match *self {
+            SpeedCurve::Constant { interval } => interval,
+            SpeedCurve::Linear { initial, decrease_per_food, min } => {
+                (initial - decrease_per_food * food_eaten as f64).max(min)
+            },
+            SpeedCurve::Stepped { initial, foods_per_step, factor, min } => {
+                let steps = food_eaten / foods_per_step.max(1);
+                (initial * factor.powi(steps as i32)).max(min)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_keeps_its_interval() {
+        let curve = SpeedCurve::Constant { interval: 0.25 };
+
+        assert_eq!(curve.interval(0), 0.25);
+        assert_eq!(curve.interval(1000), 0.25);
+    }
+
+    #[test]
+    fn linear_stops_at_its_minimum() {
+        let curve = SpeedCurve::Linear { initial: 0.5, decrease_per_food: 0.125, min: 0.2 };
+
+        assert_eq!(curve.interval(0), 0.5);
+        assert_eq!(curve.interval(1), 0.375);
+        assert_eq!(curve.interval(2), 0.25);
+        assert_eq!(curve.interval(3), 0.2);
+        assert_eq!(curve.interval(100), 0.2);
+    }
+
+    #[test]
+    fn stepped_speeds_up_every_few_meals_down_to_its_minimum() {
+        let curve = SpeedCurve::Stepped { initial: 0.5, foods_per_step: 3, factor: 0.5, min: 0.1 };
+
+        assert_eq!(curve.interval(0), 0.5);
+        assert_eq!(curve.interval(2), 0.5);
+        assert_eq!(curve.interval(3), 0.25);
+        assert_eq!(curve.interval(6), 0.125);
+        assert_eq!(curve.interval(9), 0.1);
+        assert_eq!(curve.interval(100), 0.1);
+    }
+}
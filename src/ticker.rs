@@ -0,0 +1,127 @@
+use crate::controller::Controller;
+use crate::score::RoundStats;
+use crate::{Game, GameState};
+
+/// Advances a game in real time, one step per `Game::tick_interval` seconds.
+/// Shared by all front-ends so they play at the same pace.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Ticker {
+    cooldown: f64,
+}
+
+impl Ticker {
+    pub fn new(game: &Game) -> Ticker {
+        Ticker { cooldown: game.tick_interval() }
+    }
+
+    pub fn reset(&mut self, game: &Game) {
+        self.cooldown = game.tick_interval();
+    }
+
+    /// Lets `dt` seconds pass without touching the game.
+    /// Returns true when the game is due for its next step. Call `reset` once the step is
+    /// taken, so the interval reflects the speed-up of a meal in that very step.
+    pub fn tick(&mut self, dt: f64) -> bool {
+        self.cooldown -= dt;
+        self.cooldown <= 0.0
+    }
+
+    /// Lets `dt` seconds pass. Returns the round statistics when a step ended the round.
+    pub fn update(&mut self, dt: f64, game: &mut Game) -> Option<RoundStats> {
+        self.update_with(dt, game, |_| {})
+    }
+
+    /// Like `update`, but calls `before_step` right before a step is taken,
+    /// so computer players can steer with the latest state of the game.
+    /// A paused or finished game takes no step, `before_step` is not called then.
+    pub fn update_with(
+        &mut self,
+        dt: f64,
+        game: &mut Game,
+        before_step: impl FnOnce(&mut Game),
+    ) -> Option<RoundStats> {
+        game.add_play_time(dt);
+
+        if !self.tick(dt) {
+            return None;
+        }
+
+        if game.state == GameState::Playing {
+            before_step(game);
+        }
+        let stats = game.update_game_state();
+        self.reset(game);
+        stats
+    }
+
+    /// Like `update`, with the input for the step asked from `controller`.
+    pub fn update_controlled(&mut self, dt: f64, game: &mut Game, controller: &mut dyn Controller) -> Option<RoundStats> {
+        self.update_with(dt, game, |game| {
+            if let Some(direction) = controller.next_direction(game) {
+                game.set_player_direction(controller.player(), direction);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::ScriptedController;
+    use crate::speed::SpeedCurve;
+    use crate::{BoardTopology, CellType, Direction};
+
+    #[test]
+    fn speed_up_applies_right_after_the_meal() {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        game.speed_curve = SpeedCurve::Linear { initial: 0.5, decrease_per_food: 0.25, min: 0.125 };
+        game.play(Direction::Right);
+
+        let head = game.snake().head_index;
+        game.field[head + 1].cell_type = CellType::Food;
+
+        let mut ticker = Ticker::new(&game);
+        assert!(ticker.update(0.25, &mut game).is_none());
+        assert_eq!(game.step_count, 0);
+
+        // the step taken here eats, the next one is due after the shorter interval
+        ticker.update(0.25, &mut game);
+        assert_eq!(game.stats.food_eaten, 1);
+        assert_eq!(ticker.cooldown, 0.25);
+
+        ticker.update(0.125, &mut game);
+        assert_eq!(game.step_count, 1);
+        ticker.update(0.125, &mut game);
+        assert_eq!(game.step_count, 2);
+    }
+
+    #[test]
+    fn tick_waits_for_reset() {
+        let game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        let mut ticker = Ticker::new(&game);
+
+        assert!(!ticker.tick(0.3));
+        assert!(ticker.tick(0.3));
+        ticker.reset(&game);
+        assert!(!ticker.tick(0.3));
+    }
+
+    #[test]
+    fn paused_game_does_not_ask_the_controller() {
+        let mut game = Game::new(10, 10, BoardTopology::Walled, 1).unwrap();
+        let mut script: ScriptedController = "U L D".parse().unwrap();
+        let mut ticker = Ticker::new(&game);
+
+        for _ in 0..4 {
+            ticker.update_controlled(1.0, &mut game, &mut script);
+        }
+
+        assert_eq!(game.step_count, 0);
+        assert!(game.snake().input_queue.is_empty());
+
+        game.play(Direction::Right);
+        ticker.update_controlled(1.0, &mut game, &mut script);
+        assert_eq!(game.step_count, 1);
+        assert_eq!(game.snake().direction, Direction::Up);
+    }
+}